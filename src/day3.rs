@@ -1,3 +1,4 @@
+use advent_of_rust_2019::grid::{Directions, Pos};
 use std::collections::HashMap;
 use std::fs;
 
@@ -27,14 +28,6 @@ pub fn solution(filename: &String) {
     );
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-enum Directions {
-    U,
-    D,
-    R,
-    L,
-}
-
 #[derive(Debug, PartialEq)]
 struct Move {
     direction: Directions,
@@ -67,25 +60,7 @@ impl Wire {
         let mut total_steps = 1;
         for mov in moves {
             for _ in 0..mov.steps {
-                let pos = self.current.clone();
-                let new_pos = match mov.direction {
-                    Directions::U => Pos {
-                        x: pos.x,
-                        y: pos.y + 1,
-                    },
-                    Directions::D => Pos {
-                        x: pos.x,
-                        y: pos.y - 1,
-                    },
-                    Directions::R => Pos {
-                        x: pos.x + 1,
-                        y: pos.y,
-                    },
-                    Directions::L => Pos {
-                        x: pos.x - 1,
-                        y: pos.y,
-                    },
-                };
+                let new_pos = self.current.step(mov.direction);
 
                 let min_steps: i32;
                 if self.route.contains_key(&new_pos) {
@@ -109,18 +84,6 @@ impl Wire {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct Pos {
-    x: i32,
-    y: i32,
-}
-
-impl Pos {
-    fn start() -> Pos {
-        return Pos { x: 0, y: 0 };
-    }
-}
-
 impl From<&str> for Wire {
     fn from(str: &str) -> Self {
         Wire::new_from_route(str.split(',').map(|x| Move::from(x)).collect())
@@ -0,0 +1,116 @@
+/// A point on a 2D grid, with `y` growing upwards.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Pos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pos {
+    pub fn start() -> Pos {
+        Pos { x: 0, y: 0 }
+    }
+
+    /// The neighbouring position one step in `direction`.
+    pub fn step(&self, direction: Directions) -> Pos {
+        match direction {
+            Directions::U => Pos {
+                x: self.x,
+                y: self.y + 1,
+            },
+            Directions::D => Pos {
+                x: self.x,
+                y: self.y - 1,
+            },
+            Directions::R => Pos {
+                x: self.x + 1,
+                y: self.y,
+            },
+            Directions::L => Pos {
+                x: self.x - 1,
+                y: self.y,
+            },
+        }
+    }
+}
+
+/// The smallest and largest corner of the rectangle that holds all `positions`, or
+/// `None` if there are none.
+pub fn bounds<'a>(positions: impl IntoIterator<Item = &'a Pos>) -> Option<(Pos, Pos)> {
+    positions
+        .into_iter()
+        .fold(None, |bounds, pos| match bounds {
+            None => Some((pos.clone(), pos.clone())),
+            Some((min, max)) => Some((
+                Pos {
+                    x: min.x.min(pos.x),
+                    y: min.y.min(pos.y),
+                },
+                Pos {
+                    x: max.x.max(pos.x),
+                    y: max.y.max(pos.y),
+                },
+            )),
+        })
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Directions {
+    U,
+    D,
+    R,
+    L,
+}
+
+impl Directions {
+    pub const ALL: [Directions; 4] = [Directions::U, Directions::D, Directions::L, Directions::R];
+
+    pub fn turn_left(self) -> Directions {
+        match self {
+            Directions::U => Directions::L,
+            Directions::L => Directions::D,
+            Directions::D => Directions::R,
+            Directions::R => Directions::U,
+        }
+    }
+
+    pub fn turn_right(self) -> Directions {
+        match self {
+            Directions::U => Directions::R,
+            Directions::R => Directions::D,
+            Directions::D => Directions::L,
+            Directions::L => Directions::U,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_and_turn() {
+        let pos = Pos::start().step(Directions::U).step(Directions::L);
+        assert_eq!(Pos { x: -1, y: 1 }, pos);
+        assert_eq!(Pos::start(), pos.step(Directions::R).step(Directions::D));
+
+        let mut heading = Directions::U;
+        for expected in [Directions::L, Directions::D, Directions::R, Directions::U].iter() {
+            heading = heading.turn_left();
+            assert_eq!(*expected, heading);
+        }
+        assert_eq!(
+            Directions::L,
+            Directions::U.turn_right().turn_right().turn_right()
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(None, bounds(&[]));
+        let positions = [Pos { x: 2, y: -1 }, Pos { x: -3, y: 4 }, Pos { x: 0, y: 0 }];
+        assert_eq!(
+            Some((Pos { x: -3, y: -1 }, Pos { x: 2, y: 4 })),
+            bounds(&positions)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use super::{IntcodeError, Program, State};
+
+/// A row of identical programs where every machine's output is the next machine's input.
+pub struct Amplifiers {
+    machines: Vec<Program>,
+}
+
+impl Amplifiers {
+    /// Loads one copy of `program` per phase setting, feeding each its phase as first input.
+    pub fn new(program: &Program, phases: &[i64]) -> Amplifiers {
+        let machines = phases
+            .iter()
+            .map(|phase| {
+                let mut machine = program.clone();
+                machine.push_input(*phase);
+                machine
+            })
+            .collect();
+
+        Amplifiers { machines }
+    }
+
+    /// Passes `signal` through every machine once and returns the output of the last one.
+    pub fn pipeline(&mut self, mut signal: i64) -> Result<i64, IntcodeError> {
+        for machine in self.machines.iter_mut() {
+            machine.push_input(signal);
+            signal = match machine.run()? {
+                State::Output(value) => value,
+                State::NeedsInput => return Err(IntcodeError::InputRequired { ip: machine.ip() }),
+                State::Halted => return Err(IntcodeError::UnexpectedHalt { ip: machine.ip() }),
+            };
+        }
+        Ok(signal)
+    }
+
+    /// Wires the last machine back into the first and keeps passing signals around
+    /// until a machine halts, returning the last signal the last machine sent. Without
+    /// any machines the signal comes back unchanged, as from `pipeline`.
+    pub fn feedback_loop(&mut self, mut signal: i64) -> Result<i64, IntcodeError> {
+        if self.machines.is_empty() {
+            return Ok(signal);
+        }
+        let last = self.machines.len() - 1;
+        let mut thrust = signal;
+        loop {
+            for (idx, machine) in self.machines.iter_mut().enumerate() {
+                machine.push_input(signal);
+                match machine.run()? {
+                    State::Output(value) => signal = value,
+                    State::NeedsInput => {
+                        return Err(IntcodeError::InputRequired { ip: machine.ip() })
+                    }
+                    State::Halted => return Ok(thrust),
+                }
+                if idx == last {
+                    thrust = signal;
+                }
+            }
+        }
+    }
+}
+
+/// Tries every ordering of `phases` and returns the highest thrust signal together with
+/// the phase settings that produced it.
+pub fn max_thrust(
+    program: &Program,
+    phases: &[i64],
+    feedback: bool,
+) -> Result<(i64, Vec<i64>), IntcodeError> {
+    let run = |phases: &[i64]| {
+        let mut amplifiers = Amplifiers::new(program, phases);
+        if feedback {
+            amplifiers.feedback_loop(0)
+        } else {
+            amplifiers.pipeline(0)
+        }
+    };
+
+    // the phases in the given order are the first permutation, and the only one when
+    // there are no phases
+    let mut best = (run(phases)?, phases.to_vec());
+    for phases in permutations(phases).into_iter().skip(1) {
+        let thrust = run(&phases)?;
+        if thrust > best.0 {
+            best = (thrust, phases);
+        }
+    }
+    Ok(best)
+}
+
+fn permutations(values: &[i64]) -> Vec<Vec<i64>> {
+    if values.len() <= 1 {
+        return vec![values.to_vec()];
+    }
+
+    let mut result = vec![];
+    for (idx, value) in values.iter().enumerate() {
+        let mut rest = values.to_vec();
+        rest.remove(idx);
+        for mut permutation in permutations(&rest) {
+            permutation.insert(0, *value);
+            result.push(permutation);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+
+    #[test]
+    fn test_permutations() {
+        assert_eq!(6, permutations(&[0, 1, 2]).len());
+        assert_eq!(120, permutations(&[0, 1, 2, 3, 4]).len());
+        assert_eq!(vec![vec![1, 2], vec![2, 1]], permutations(&[1, 2]));
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let program = Program::from("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0");
+        assert_eq!(
+            Ok(43210),
+            Amplifiers::new(&program, &[4, 3, 2, 1, 0]).pipeline(0)
+        );
+        assert_eq!(
+            Ok((43210, vec![4, 3, 2, 1, 0])),
+            max_thrust(&program, &[0, 1, 2, 3, 4], false)
+        );
+
+        let program = Program::from(
+            "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+        );
+        assert_eq!(
+            Ok((54321, vec![0, 1, 2, 3, 4])),
+            max_thrust(&program, &[0, 1, 2, 3, 4], false)
+        );
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let program = Program::from(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        );
+        assert_eq!(
+            Ok(139629729),
+            Amplifiers::new(&program, &[9, 8, 7, 6, 5]).feedback_loop(0)
+        );
+        assert_eq!(
+            Ok((139629729, vec![9, 8, 7, 6, 5])),
+            max_thrust(&program, &[5, 6, 7, 8, 9], true)
+        );
+    }
+
+    #[test]
+    fn test_early_halt() {
+        // adds 1 to every signal, and halts after the first one when its phase is 0
+        let program = Program::from(
+            assemble(
+                "
+                        IN -> [phase]
+                loop:   IN -> [x]
+                        ADD [x], #1 -> [x]
+                        OUT [x]
+                        JNZ [phase], #loop
+                        HALT
+                phase:  DATA 0
+                x:      DATA 0
+                ",
+            )
+            .unwrap()
+            .as_str(),
+        );
+        assert_eq!(Ok(9), Amplifiers::new(&program, &[0, 1]).feedback_loop(7));
+        assert_eq!(Ok(9), Amplifiers::new(&program, &[1, 0]).feedback_loop(7));
+    }
+
+    #[test]
+    fn test_no_phases() {
+        let program = Program::from("3,0,4,0,99");
+        assert_eq!(Ok(7), Amplifiers::new(&program, &[]).feedback_loop(7));
+        assert_eq!(Ok((0, vec![])), max_thrust(&program, &[], false));
+        assert_eq!(Ok((0, vec![])), max_thrust(&program, &[], true));
+    }
+}
@@ -0,0 +1,272 @@
+use super::{IntcodeError, Program, State};
+
+/// What the cabinet draws at a position of the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Tile {
+    fn symbol(self) -> char {
+        match self {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::Paddle => '-',
+            Tile::Ball => 'o',
+        }
+    }
+}
+
+/// Drives a program as an arcade cabinet. The program draws with `(x, y, tile)` output
+/// triples, except that `(-1, 0, score)` shows the score, and reads the joystick:
+/// -1 for left, 0 for neutral and 1 for right.
+pub struct Arcade {
+    program: Program,
+    /// Rows of the screen, top row first.
+    screen: Vec<Vec<Tile>>,
+    score: i64,
+    /// Values of a triple the program has only partly output so far.
+    pending: Vec<i64>,
+    halted: bool,
+}
+
+impl Arcade {
+    pub fn new(program: Program) -> Arcade {
+        Arcade {
+            program,
+            screen: vec![],
+            score: 0,
+            pending: vec![],
+            halted: false,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Sets memory address 0 to `quarters` before the game starts, which is how the
+    /// cabinet is made to play for free.
+    pub fn insert_quarters(&mut self, quarters: i64) {
+        self.program.set(0, quarters);
+    }
+
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn tile(&self, x: usize, y: usize) -> Tile {
+        self.screen
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or(Tile::Empty)
+    }
+
+    /// Number of `tile`s on the screen.
+    pub fn count(&self, tile: Tile) -> usize {
+        self.screen
+            .iter()
+            .flatten()
+            .filter(|other| **other == tile)
+            .count()
+    }
+
+    /// Runs the program until it reads the joystick or halts, drawing as it goes.
+    /// Fails with `UnexpectedOutput` for a tile id or position that cannot be drawn.
+    pub fn run(&mut self) -> Result<(), IntcodeError> {
+        loop {
+            match self.program.run()? {
+                State::Output(value) => {
+                    self.pending.push(value);
+                    if let [x, y, value] = self.pending[..] {
+                        self.pending.clear();
+                        self.draw(x, y, value)?;
+                    }
+                }
+                State::NeedsInput => return Ok(()),
+                State::Halted => {
+                    self.halted = true;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Holds the joystick at `position` and runs until the program reads it again.
+    pub fn joystick(&mut self, position: i64) -> Result<(), IntcodeError> {
+        self.program.push_input(position);
+        self.run()
+    }
+
+    /// The joystick position that moves the paddle towards the ball.
+    pub fn track_ball(&self) -> i64 {
+        match (self.find(Tile::Ball), self.find(Tile::Paddle)) {
+            (Some((ball, _)), Some((paddle, _))) => (ball as i64 - paddle as i64).signum(),
+            _ => 0,
+        }
+    }
+
+    /// Plays until the game is over, following the ball with the paddle, and returns
+    /// the final score.
+    pub fn autoplay(&mut self) -> Result<i64, IntcodeError> {
+        self.run()?;
+        while !self.halted {
+            self.joystick(self.track_ball())?;
+        }
+        Ok(self.score)
+    }
+
+    /// The screen as text, followed by a line with the score.
+    pub fn render(&self) -> String {
+        let mut text: String = self
+            .screen
+            .iter()
+            .map(|row| {
+                let mut line: String = row.iter().map(|tile| tile.symbol()).collect();
+                line.push('\n');
+                line
+            })
+            .collect();
+        text.push_str(&format!("Score: {}\n", self.score));
+        text
+    }
+
+    fn draw(&mut self, x: i64, y: i64, value: i64) -> Result<(), IntcodeError> {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+            return Ok(());
+        }
+
+        let ip = self.program.instruction;
+        let tile = match value {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::Paddle,
+            4 => Tile::Ball,
+            _ => return Err(IntcodeError::UnexpectedOutput { ip, value }),
+        };
+        for coordinate in [x, y].iter() {
+            if *coordinate < 0 {
+                return Err(IntcodeError::UnexpectedOutput {
+                    ip,
+                    value: *coordinate,
+                });
+            }
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        if y >= self.screen.len() {
+            self.screen.resize(y + 1, vec![]);
+        }
+        let width = self.screen.iter().map(|row| row.len()).max().unwrap_or(0);
+        let width = width.max(x + 1);
+        for row in self.screen.iter_mut() {
+            row.resize(width, Tile::Empty);
+        }
+        self.screen[y][x] = tile;
+        Ok(())
+    }
+
+    fn find(&self, tile: Tile) -> Option<(usize, usize)> {
+        self.screen
+            .iter()
+            .enumerate()
+            .find_map(|(y, row)| row.iter().position(|other| *other == tile).map(|x| (x, y)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+
+    // draws a ball over a block between two walls, then moves the paddle once by the
+    // joystick and scores 1000 times its new position
+    const GAME: &str = "
+                OUT #0
+                OUT #0
+                OUT #1
+                OUT #2
+                OUT #0
+                OUT #1
+                OUT #1
+                OUT #0
+                OUT #2
+                OUT #1
+                OUT #1
+                OUT #4
+                OUT #0
+                OUT #2
+                OUT #3
+                OUT #-1
+                OUT #0
+                OUT #0
+                IN -> [joystick]
+                OUT #0
+                OUT #2
+                OUT #0
+                ADD [joystick], #0 -> [paddle]
+                OUT [paddle]
+                OUT #2
+                OUT #3
+                MUL [paddle], #1000 -> [score]
+                OUT #-1
+                OUT #0
+                OUT [score]
+                HALT
+        joystick: DATA 0
+        paddle: DATA 0
+        score:  DATA 0
+    ";
+
+    fn game() -> Program {
+        Program::from(assemble(GAME).unwrap().as_str())
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut arcade = Arcade::new(game());
+        arcade.run().unwrap();
+        assert!(!arcade.halted());
+        assert_eq!(Tile::Ball, arcade.tile(1, 1));
+        assert_eq!(Tile::Empty, arcade.tile(5, 5));
+        assert_eq!(2, arcade.count(Tile::Wall));
+        assert_eq!(1, arcade.count(Tile::Block));
+        assert_eq!("#=#\n o \n-  \nScore: 0\n", arcade.render());
+
+        assert_eq!(
+            Err(IntcodeError::UnexpectedOutput { ip: 52, value: -1 }),
+            arcade.joystick(-1)
+        );
+    }
+
+    #[test]
+    fn test_autoplay() {
+        let mut arcade = Arcade::new(game());
+        arcade.run().unwrap();
+        assert_eq!(1, arcade.track_ball());
+        assert_eq!(Ok(1000), arcade.autoplay());
+        assert!(arcade.halted());
+        assert_eq!("#=#\n o \n - \nScore: 1000\n", arcade.render());
+    }
+
+    #[test]
+    fn test_insert_quarters() {
+        let mut arcade = Arcade::new(Program::from("1,0,0,0,99"));
+        arcade.insert_quarters(2);
+        assert_eq!(2, arcade.program().get(0));
+        arcade.run().unwrap();
+        assert_eq!(4, arcade.program().get(0));
+    }
+}
@@ -0,0 +1,117 @@
+use super::{IntcodeError, Program, State};
+
+/// What an ASCII program printed: every output value from 0 to 127 as text, and any
+/// other value, like a puzzle answer printed after the text, on its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transcript {
+    pub text: String,
+    pub values: Vec<i64>,
+}
+
+/// Drives a program that reads and writes text, one ASCII code per value.
+pub struct Ascii {
+    program: Program,
+    halted: bool,
+}
+
+impl Ascii {
+    pub fn new(program: Program) -> Ascii {
+        Ascii {
+            program,
+            halted: false,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Whether the program has halted.
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Queues `line` as input, one value per character followed by a newline. Fails
+    /// with `NotAscii`, queueing nothing, if `line` has a character that is not ASCII.
+    pub fn send(&mut self, line: &str) -> Result<(), IntcodeError> {
+        if let Some((index, char)) = line.char_indices().find(|(_, char)| !char.is_ascii()) {
+            return Err(IntcodeError::NotAscii { index, char });
+        }
+        for char in line.chars().chain(Some('\n')) {
+            self.program.push_input(char as i64);
+        }
+        Ok(())
+    }
+
+    /// Runs until the program needs more input or halts and returns what it printed.
+    pub fn run(&mut self) -> Result<Transcript, IntcodeError> {
+        let mut transcript = Transcript::default();
+        loop {
+            match self.program.run()? {
+                State::Output(value) if (0..128).contains(&value) => {
+                    transcript.text.push(value as u8 as char)
+                }
+                State::Output(value) => transcript.values.push(value),
+                State::NeedsInput => return Ok(transcript),
+                State::Halted => {
+                    self.halted = true;
+                    return Ok(transcript);
+                }
+            }
+        }
+    }
+
+    /// Sends `line` and runs until the program needs more input or halts.
+    pub fn execute(&mut self, line: &str) -> Result<Transcript, IntcodeError> {
+        self.send(line)?;
+        self.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // echoes its input in upper case until it reads a full stop, then prints 1000
+    const SHOUT: &str = "\
+        3,100,1008,100,46,101,1005,101,25,1007,100,97,101,1005,101,20,\
+        1001,100,-32,100,4,100,1105,1,0,104,1000,99";
+
+    #[test]
+    fn test_text_and_values() {
+        let mut ascii = Ascii::new(Program::from(SHOUT));
+        assert_eq!(Transcript::default(), ascii.run().unwrap());
+
+        let transcript = ascii.execute("Hi, you").unwrap();
+        assert_eq!("HI, YOU\n", transcript.text);
+        assert!(transcript.values.is_empty());
+        assert!(!ascii.halted());
+
+        let transcript = ascii.execute(".").unwrap();
+        assert_eq!("", transcript.text);
+        assert_eq!(vec![1000], transcript.values);
+        assert!(ascii.halted());
+    }
+
+    #[test]
+    fn test_send() {
+        let mut ascii = Ascii::new(Program::from("3,0,99"));
+        ascii.send("AB").unwrap();
+        assert_eq!(
+            Err(IntcodeError::NotAscii {
+                index: 1,
+                char: 'é'
+            }),
+            ascii.send("Té")
+        );
+        assert_eq!(
+            vec![65, 66, 10],
+            ascii
+                .program()
+                .input()
+                .iter()
+                .cloned()
+                .collect::<Vec<i64>>()
+        );
+    }
+}
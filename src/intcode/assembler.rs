@@ -0,0 +1,370 @@
+use super::instruction::MNEMONICS;
+use super::Mnemonic;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// A problem in assembler source, with the 1-based line it was found on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AssemblerError {
+    UnknownMnemonic {
+        line: usize,
+        name: String,
+    },
+    OperandCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidOperand {
+        line: usize,
+        operand: String,
+    },
+    ImmediateWrite {
+        line: usize,
+        operand: String,
+    },
+    UnknownLabel {
+        line: usize,
+        label: String,
+    },
+    DuplicateLabel {
+        line: usize,
+        label: String,
+    },
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssemblerError::UnknownMnemonic { line, name } => {
+                write!(f, "line {}: unknown mnemonic {:?}", line, name)
+            }
+            AssemblerError::OperandCount {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} operands, found {}",
+                line, expected, found
+            ),
+            AssemblerError::InvalidOperand { line, operand } => {
+                write!(f, "line {}: invalid operand {:?}", line, operand)
+            }
+            AssemblerError::ImmediateWrite { line, operand } => {
+                write!(f, "line {}: cannot write to immediate {:?}", line, operand)
+            }
+            AssemblerError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label {:?}", line, label)
+            }
+            AssemblerError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label {:?} defined twice", line, label)
+            }
+        }
+    }
+}
+
+impl Error for AssemblerError {}
+
+/// A statement on one source line, before labels are resolved.
+enum Statement<'a> {
+    Instruction {
+        mnemonic: &'static Mnemonic,
+        operands: Vec<&'a str>,
+    },
+    Data(Vec<&'a str>),
+}
+
+struct Line<'a> {
+    number: usize,
+    statement: Statement<'a>,
+}
+
+impl<'a> Line<'a> {
+    fn len(&self) -> usize {
+        match &self.statement {
+            Statement::Instruction { mnemonic, .. } => 1 + mnemonic.arity(),
+            Statement::Data(values) => values.len(),
+        }
+    }
+}
+
+/// Assembles mnemonic source into the comma separated text `Program::from` loads.
+///
+/// Each line holds an optional `label:`, then an instruction or a `DATA` directive, and
+/// an optional `; comment`. Operands are written like the disassembler prints them:
+/// `[address]` for position mode, `#value` for immediate mode and `[rb+offset]` for
+/// relative mode, with the written operand after `->`:
+///
+/// ```text
+///         IN -> [counter]
+/// loop:   OUT [counter]
+///         ADD [counter], #-1 -> [counter]
+///         JNZ [counter], #loop
+///         HALT
+/// counter: DATA 0
+/// ```
+///
+/// Addresses and values can be numbers or labels. Numeric prefixes like the `12:` in
+/// disassembler listings are ignored, so a listing can be fed back in.
+pub fn assemble(source: &str) -> Result<String, AssemblerError> {
+    let mut labels = HashMap::new();
+    let mut lines = vec![];
+    let mut address = 0;
+
+    for (idx, text) in source.lines().enumerate() {
+        let number = idx + 1;
+        let mut text = text.split(';').next().unwrap_or("").trim();
+
+        while let Some((label, rest)) = split_label(text) {
+            if !label.chars().all(|char| char.is_ascii_digit())
+                && labels.insert(label, address as i64).is_some()
+            {
+                return Err(AssemblerError::DuplicateLabel {
+                    line: number,
+                    label: String::from(label),
+                });
+            }
+            text = rest;
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let line = Line {
+            number,
+            statement: parse_statement(number, text)?,
+        };
+        address += line.len();
+        lines.push(line);
+    }
+
+    let mut output = vec![];
+    for line in lines {
+        match line.statement {
+            Statement::Instruction { mnemonic, operands } => {
+                let mut opcode = mnemonic.opcode;
+                let mut args = vec![];
+                for (arg, operand) in operands.into_iter().enumerate() {
+                    let (mode, value) = parse_operand(line.number, operand, &labels)?;
+                    if mode == 1 && arg >= mnemonic.reads {
+                        return Err(AssemblerError::ImmediateWrite {
+                            line: line.number,
+                            operand: String::from(operand),
+                        });
+                    }
+                    opcode += mode * 10i64.pow(arg as u32 + 2);
+                    args.push(value);
+                }
+                output.push(opcode);
+                output.extend(args);
+            }
+            Statement::Data(values) => {
+                for value in values {
+                    output.push(resolve(line.number, value, &labels)?);
+                }
+            }
+        }
+    }
+
+    Ok(output
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(","))
+}
+
+/// Splits a leading `label:` off a line.
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let (label, rest) = text.split_at(text.find(':')?);
+    let label = label.trim();
+    if label.is_empty()
+        || !label
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+    {
+        return None;
+    }
+    Some((label, rest[1..].trim()))
+}
+
+fn parse_statement(line: usize, text: &str) -> Result<Statement<'_>, AssemblerError> {
+    let (name, rest) = match text.find(char::is_whitespace) {
+        Some(idx) => text.split_at(idx),
+        None => (text, ""),
+    };
+    let operands: Vec<&str> = rest
+        .split("->")
+        .flat_map(|part| part.split(','))
+        .map(|operand| operand.trim())
+        .filter(|operand| !operand.is_empty())
+        .collect();
+
+    if name.eq_ignore_ascii_case("DATA") {
+        return Ok(Statement::Data(operands));
+    }
+
+    let mnemonic = MNEMONICS
+        .iter()
+        .find(|mnemonic| mnemonic.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| AssemblerError::UnknownMnemonic {
+            line,
+            name: String::from(name),
+        })?;
+
+    if operands.len() != mnemonic.arity() {
+        return Err(AssemblerError::OperandCount {
+            line,
+            expected: mnemonic.arity(),
+            found: operands.len(),
+        });
+    }
+
+    Ok(Statement::Instruction { mnemonic, operands })
+}
+
+/// Parses an operand into its mode and value.
+fn parse_operand(
+    line: usize,
+    operand: &str,
+    labels: &HashMap<&str, i64>,
+) -> Result<(i64, i64), AssemblerError> {
+    if let Some(value) = operand.strip_prefix('#') {
+        return Ok((1, resolve(line, value, labels)?));
+    }
+
+    let inner = operand
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(|inner| inner.trim())
+        .ok_or_else(|| AssemblerError::InvalidOperand {
+            line,
+            operand: String::from(operand),
+        })?;
+
+    match inner.strip_prefix("rb") {
+        Some(offset) if offset.starts_with('+') => Ok((2, resolve(line, &offset[1..], labels)?)),
+        Some(offset) if offset.starts_with('-') => Ok((2, resolve(line, offset, labels)?)),
+        _ => Ok((0, resolve(line, inner, labels)?)),
+    }
+}
+
+fn resolve(line: usize, value: &str, labels: &HashMap<&str, i64>) -> Result<i64, AssemblerError> {
+    let value = value.trim();
+    if let Ok(number) = value.parse() {
+        return Ok(number);
+    }
+    if value.is_empty()
+        || !value
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_')
+    {
+        return Err(AssemblerError::InvalidOperand {
+            line,
+            operand: String::from(value),
+        });
+    }
+    labels
+        .get(value)
+        .copied()
+        .ok_or_else(|| AssemblerError::UnknownLabel {
+            line,
+            label: String::from(value),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::disassembler::disassemble;
+    use super::super::Program;
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        assert_eq!(
+            "1002,4,3,4,33",
+            assemble("MUL [4], #3 -> [4]\nDATA 33").unwrap()
+        );
+        assert_eq!(
+            "1108,-1,8,3,21101,1,2,-3,99",
+            assemble("eq #-1, #8, [3]\nadd #1, #2 -> [rb-3] ; comment\nhalt").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_labels() {
+        let source = "
+            ; counts down from the input to 1
+                    IN -> [counter]
+            loop:   OUT [counter]
+                    ADD [counter], #-1 -> [counter]
+                    JNZ [counter], #loop
+                    HALT
+            counter: DATA 0
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!("3,12,4,12,1001,12,-1,12,1005,12,2,99,0", program);
+        assert_eq!(
+            "3\n2\n1\n",
+            Program::from(program.as_str()).execute("3").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = "3,9,8,9,10,9,4,9,99,-1,8";
+        assert_eq!(program, assemble(&disassemble(program).unwrap()).unwrap());
+
+        let program = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        assert_eq!(program, assemble(&disassemble(program).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err(AssemblerError::UnknownMnemonic {
+                line: 2,
+                name: String::from("NOP")
+            }),
+            assemble("HALT\nNOP")
+        );
+        assert_eq!(
+            Err(AssemblerError::OperandCount {
+                line: 1,
+                expected: 3,
+                found: 2
+            }),
+            assemble("ADD #1, #2")
+        );
+        assert_eq!(
+            Err(AssemblerError::ImmediateWrite {
+                line: 1,
+                operand: String::from("#3")
+            }),
+            assemble("ADD #1, #2 -> #3")
+        );
+        assert_eq!(
+            Err(AssemblerError::UnknownLabel {
+                line: 1,
+                label: String::from("nowhere")
+            }),
+            assemble("JNZ #1, #nowhere")
+        );
+        assert_eq!(
+            Err(AssemblerError::DuplicateLabel {
+                line: 2,
+                label: String::from("a")
+            }),
+            assemble("a: HALT\na: HALT")
+        );
+        assert_eq!(
+            Err(AssemblerError::InvalidOperand {
+                line: 1,
+                operand: String::from("4")
+            }),
+            assemble("OUT 4")
+        );
+    }
+}
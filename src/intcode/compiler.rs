@@ -0,0 +1,351 @@
+use super::{to_address, to_number, ArgumentModes, IntcodeError, Mnemonic, Program, State, Word};
+
+type Operand<W> = Box<dyn Fn(&Program<W>) -> Result<W, IntcodeError>>;
+type Target<W> = Box<dyn Fn(&Program<W>) -> Result<usize, IntcodeError>>;
+type Op<W> = Box<dyn Fn(&mut Program<W>) -> Result<Option<State<W>>, IntcodeError>>;
+
+/// An instruction turned into a closure, valid for as long as memory still holds
+/// `opcode` at its address.
+struct Compiled<W: Word> {
+    opcode: W,
+    /// The opcode without its parameter modes, for `Program::opcode_counts`.
+    kind: usize,
+    op: Op<W>,
+}
+
+/// A program compiled ahead of time into one closure per instruction, with the opcode
+/// dispatch and parameter modes resolved up front.
+///
+/// Operands are still read from memory when an instruction runs, so writes to them
+/// behave as in the interpreter. Whenever the opcode at the instruction pointer is not
+/// the one that was compiled there, because the program or the caller wrote over it or
+/// execution left the compiled image, that instruction falls back to the interpreter.
+///
+/// One `CompiledProgram` can run any number of copies of the program it was compiled
+/// from, e.g. every noun and verb of a parameter sweep.
+pub struct CompiledProgram<W: Word = i64> {
+    code: Vec<Option<Compiled<W>>>,
+}
+
+/// Compiles every cell of the loaded image of `program` that decodes as a valid
+/// instruction.
+pub fn compile<W: Word + 'static>(program: &Program<W>) -> CompiledProgram<W> {
+    let code = program
+        .memory
+        .image()
+        .iter()
+        .enumerate()
+        .map(|(address, opcode)| {
+            let number = opcode.to_i64()?;
+            let op = compile_instruction(address, number)?;
+            Some(Compiled {
+                opcode: opcode.clone(),
+                kind: number as usize % 100,
+                op,
+            })
+        })
+        .collect();
+
+    CompiledProgram { code }
+}
+
+impl<W: Word + 'static> CompiledProgram<W> {
+    /// Like `Program::run`, but through the compiled instructions, which are the
+    /// built-in ones. Programs with registered instructions, a tracer, an undo journal,
+    /// code write tracking or execution limits run in the interpreter, which handles
+    /// them.
+    pub fn run(&self, program: &mut Program<W>) -> Result<State<W>, IntcodeError> {
+        if program.instructions.is_custom()
+            || program.trace.is_enabled()
+            || program.journal.is_some()
+            || program.code_writes.is_some()
+            || program.limits.is_set()
+        {
+            return program.run();
+        }
+
+        loop {
+            if program.halted {
+                return Ok(State::Halted);
+            }
+            let ip = program.index;
+            let state = match self.code.get(ip) {
+                Some(Some(compiled)) if *program.memory.get(ip) == compiled.opcode => {
+                    program.instruction = ip;
+                    let state = (compiled.op)(program)?;
+                    if state != Some(State::NeedsInput) {
+                        program.count(compiled.kind);
+                    }
+                    state
+                }
+                _ => program.step()?,
+            };
+            if let Some(state) = state {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// Like `Program::execute`, but through the compiled instructions.
+    pub fn execute(&self, program: &mut Program<W>, input: &str) -> Result<String, IntcodeError> {
+        program.execute_with(input, |program| self.run(program))
+    }
+
+    /// Number of addresses that were compiled.
+    pub fn len(&self) -> usize {
+        self.code
+            .iter()
+            .filter(|compiled| compiled.is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn compile_instruction<W: Word + 'static>(address: usize, opcode: i64) -> Option<Op<W>> {
+    if opcode < 0 {
+        return None;
+    }
+    let mnemonic = Mnemonic::find(opcode % 100)?;
+    let modes = ArgumentModes::new(opcode);
+    if modes.unused_mode(mnemonic.arity()).is_some() {
+        return None;
+    }
+
+    let mut operands: Vec<Operand<W>> = vec![];
+    for arg in 0..mnemonic.reads {
+        operands.push(operand(address + 1 + arg, modes.get_mode(arg))?);
+    }
+    let target = if mnemonic.writes {
+        Some(target(
+            address + 1 + mnemonic.reads,
+            modes.get_mode(mnemonic.reads),
+        )?)
+    } else {
+        None
+    };
+
+    let next = address + 1 + mnemonic.arity();
+    let mut operands = operands.into_iter();
+    let op: Op<W> = match (opcode % 100, operands.next(), operands.next(), target) {
+        (1, Some(a), Some(b), Some(target)) => Box::new(move |program| {
+            let (a, b) = (a(program)?, b(program)?);
+            let position = target(program)?;
+            let value = a.checked_add(&b).ok_or(IntcodeError::Overflow {
+                ip: program.instruction,
+            })?;
+            program.store(position, value);
+            program.index = next;
+            Ok(None)
+        }),
+        (2, Some(a), Some(b), Some(target)) => Box::new(move |program| {
+            let (a, b) = (a(program)?, b(program)?);
+            let position = target(program)?;
+            let value = a.checked_mul(&b).ok_or(IntcodeError::Overflow {
+                ip: program.instruction,
+            })?;
+            program.store(position, value);
+            program.index = next;
+            Ok(None)
+        }),
+        (3, None, None, Some(target)) => Box::new(move |program| {
+            let position = target(program)?;
+            match program.input.pop_front() {
+                Some(value) => program.store(position, value),
+                None => return Ok(Some(State::NeedsInput)),
+            }
+            program.index = next;
+            Ok(None)
+        }),
+        (4, Some(a), None, None) => Box::new(move |program| {
+            let value = a(program)?;
+            program.output.push(value.clone());
+            program.index = next;
+            Ok(Some(State::Output(value)))
+        }),
+        (5, Some(a), Some(b), None) => jump(a, b, true, next),
+        (6, Some(a), Some(b), None) => jump(a, b, false, next),
+        (7, Some(a), Some(b), Some(target)) => compare(a, b, target, |a, b| a < b, next),
+        (8, Some(a), Some(b), Some(target)) => compare(a, b, target, |a, b| a == b, next),
+        (9, Some(a), None, None) => Box::new(move |program| {
+            let offset = to_number(&a(program)?, program.instruction)?;
+            program.relative_base =
+                program
+                    .relative_base
+                    .checked_add(offset)
+                    .ok_or(IntcodeError::Overflow {
+                        ip: program.instruction,
+                    })?;
+            program.index = next;
+            Ok(None)
+        }),
+        (99, None, None, None) => Box::new(|program| {
+            program.halted = true;
+            Ok(Some(State::Halted))
+        }),
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn jump<W: Word + 'static>(a: Operand<W>, b: Operand<W>, is_not_zero: bool, next: usize) -> Op<W> {
+    Box::new(move |program| {
+        let (a, b) = (a(program)?, b(program)?);
+        program.index = if is_not_zero != a.is_zero() {
+            let ip = program.instruction;
+            to_address(to_number(&b, ip)?, ip)?
+        } else {
+            next
+        };
+        Ok(None)
+    })
+}
+
+fn compare<W: Word + 'static>(
+    a: Operand<W>,
+    b: Operand<W>,
+    target: Target<W>,
+    operator: fn(&W, &W) -> bool,
+    next: usize,
+) -> Op<W> {
+    Box::new(move |program| {
+        let (a, b) = (a(program)?, b(program)?);
+        let position = target(program)?;
+        let value = if operator(&a, &b) { 1 } else { 0 };
+        program.store(position, W::from_i64(value));
+        program.index = next;
+        Ok(None)
+    })
+}
+
+/// Reads the argument stored at `cell` according to `mode`.
+fn operand<W: Word + 'static>(cell: usize, mode: u32) -> Option<Operand<W>> {
+    if mode == 1 {
+        return Some(Box::new(move |program| {
+            Ok(program.memory.get(cell).clone())
+        }));
+    }
+    let target = target(cell, mode)?;
+    Some(Box::new(move |program| {
+        Ok(program.memory.get(target(program)?).clone())
+    }))
+}
+
+/// Resolves the address the argument stored at `cell` points to according to `mode`.
+fn target<W: Word + 'static>(cell: usize, mode: u32) -> Option<Target<W>> {
+    match mode {
+        0 => Some(Box::new(move |program| {
+            let ip = program.instruction;
+            to_address(to_number(program.memory.get(cell), ip)?, ip)
+        })),
+        2 => Some(Box::new(move |program| {
+            let ip = program.instruction;
+            let offset = to_number(program.memory.get(cell), ip)?;
+            let address = program
+                .relative_base
+                .checked_add(offset)
+                .ok_or(IntcodeError::Overflow { ip })?;
+            to_address(address, ip)
+        })),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_interpreter() {
+        let programs = [
+            ("3,9,8,9,10,9,4,9,99,-1,8", "8"),
+            ("3,3,1107,-1,8,3,4,3,99", "7"),
+            ("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9", "0"),
+            (
+                "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+                "",
+            ),
+            (
+                "1001,18,3,18,1101,1002,0,0,1007,18,10,19,1005,19,0,4,18,99,2,0",
+                "",
+            ),
+        ];
+        for (text, input) in programs.iter() {
+            let program = Program::from(*text);
+            let compiled = compile(&program);
+            assert_eq!(
+                program.clone().execute(input),
+                compiled.execute(&mut program.clone(), input)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parameter_sweep() {
+        let program = Program::from("1,0,0,0,99,15,20,0,99");
+        let compiled = compile(&program);
+        assert_eq!(3, compiled.len());
+
+        let mut attempt = program.clone();
+        attempt.set(1, 5);
+        attempt.set(2, 6);
+        assert_eq!(Ok(State::Halted), compiled.run(&mut attempt));
+        assert_eq!(35, attempt.get(0));
+    }
+
+    #[test]
+    fn test_fallback() {
+        // overwrites the halt at 6 with an output of 7
+        let program = Program::from("1101,4,100,6,104,99,99,7");
+        let compiled = compile(&program);
+        let mut prog = program.clone();
+        assert_eq!(Ok(State::Output(99)), compiled.run(&mut prog));
+        assert_eq!(Ok(State::Output(7)), compiled.run(&mut prog));
+        assert_eq!(
+            Err(IntcodeError::UnknownOpcode { ip: 8, opcode: 0 }),
+            compiled.run(&mut prog)
+        );
+
+        let mut prog = program.clone();
+        prog.set(0, 1102);
+        assert_eq!(Ok(State::Output(99)), compiled.run(&mut prog));
+        assert_eq!(400, prog.get(6));
+
+        assert_eq!(vec![(2, 1), (4, 1)], prog.opcode_counts());
+
+        let halting = Program::from("1101,1,1,5,99,0");
+        let mut prog = halting.clone();
+        assert_eq!(Ok(State::Halted), compile(&halting).run(&mut prog));
+        assert_eq!(Ok(State::Halted), compile(&halting).run(&mut prog));
+        assert_eq!(vec![(1, 1), (99, 1)], prog.opcode_counts());
+
+        let mut prog = program.clone();
+        prog.enable_journal();
+        compiled.run(&mut prog).unwrap();
+        assert_eq!(2, prog.journal_len());
+
+        let program = Program::from("3,0,99");
+        assert_eq!(
+            Err(IntcodeError::InputRequired { ip: 0 }),
+            compile(&program).execute(&mut program.clone(), "")
+        );
+    }
+
+    #[test]
+    fn test_relative_base_overflow() {
+        for text in [
+            "109,9223372036854775807,109,1,99",
+            "109,9223372036854775807,204,1,99",
+        ]
+        .iter()
+        {
+            let program = Program::from(*text);
+            assert_eq!(
+                Err(IntcodeError::Overflow { ip: 2 }),
+                compile(&program).run(&mut program.clone())
+            );
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use super::disassembler::describe;
+use super::trace::TraceEvent;
+use super::{Program, State};
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{channel, Receiver};
+
+const HELP: &str = "\
+step [n]            execute n instructions (default 1) and show each one
+continue            run until a breakpoint, watchpoint, missing input or halt
+back [n]            undo the last n instructions (default 1)
+reverse             undo instructions until a breakpoint or watchpoint
+break <addr>        stop before executing the instruction at addr
+delete <addr>       remove the breakpoint at addr
+watch <addr>        stop after any instruction writes to addr
+unwatch <addr>      remove the watchpoint on addr
+list [n]            show the next n instructions (default 5)
+regs                show ip, relative base and queued input
+dump <addr> [len]   show len memory cells from addr (default 10)
+patch <addr> <val>  overwrite the memory cell at addr
+input <v>[,<v>..]   queue input values
+quit                leave the debugger
+";
+
+/// Interactive debugger around a `Program`.
+///
+/// Commands are fed one line at a time with `command`, or read from a stream with
+/// `repl`. Every command has a one letter shorthand, e.g. `s` for `step`. The program's
+/// undo journal is switched on, so `back` and `reverse` can step execution backwards.
+pub struct Debugger {
+    program: Program,
+    breakpoints: BTreeSet<usize>,
+    watchpoints: BTreeSet<usize>,
+    events: Receiver<TraceEvent>,
+}
+
+/// Whether a single step lets `continue` keep going.
+enum Flow {
+    Continue,
+    Stop,
+}
+
+impl Debugger {
+    pub fn new(mut program: Program) -> Debugger {
+        let (sender, events) = channel();
+        program.set_tracer(sender);
+        program.enable_journal();
+
+        Debugger {
+            program,
+            breakpoints: BTreeSet::new(),
+            watchpoints: BTreeSet::new(),
+            events,
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Reads commands from `input` until `quit` or the end of the stream.
+    pub fn repl<R: BufRead, T: Write>(&mut self, input: R, mut output: T) -> io::Result<()> {
+        write!(output, "(icdb) ")?;
+        output.flush()?;
+        for line in input.lines() {
+            match self.command(&line?) {
+                Some(response) => write!(output, "{}(icdb) ", response)?,
+                None => break,
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Executes one command line and returns what it printed, or `None` on `quit`.
+    pub fn command(&mut self, line: &str) -> Option<String> {
+        let mut out = String::new();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let args: Vec<Option<i64>> = words.iter().skip(1).map(|word| word.parse().ok()).collect();
+        let address = args
+            .first()
+            .copied()
+            .flatten()
+            .filter(|address| *address >= 0);
+
+        match (words.first().copied().unwrap_or(""), address) {
+            ("", _) => {}
+            ("q", _) | ("quit", _) => return None,
+            ("h", _) | ("help", _) => out.push_str(HELP),
+            ("s", _) | ("step", _) => {
+                let count = address.unwrap_or(1);
+                for _ in 0..count {
+                    if let Flow::Stop = self.step(&mut out, true) {
+                        break;
+                    }
+                }
+            }
+            ("c", _) | ("continue", _) => self.resume(&mut out),
+            ("k", _) | ("back", _) => {
+                for _ in 0..address.unwrap_or(1) {
+                    if let Flow::Stop = self.step_back(&mut out) {
+                        break;
+                    }
+                }
+                let (text, _) = describe(&self.program, self.program.ip());
+                writeln!(out, "{:>5}: {}", self.program.ip(), text).unwrap();
+            }
+            ("v", _) | ("reverse", _) => self.reverse(&mut out),
+            ("b", Some(address)) | ("break", Some(address)) => {
+                self.breakpoints.insert(address as usize);
+                writeln!(out, "breakpoint at {}", address).unwrap();
+            }
+            ("d", Some(address)) | ("delete", Some(address)) => {
+                self.breakpoints.remove(&(address as usize));
+            }
+            ("w", Some(address)) | ("watch", Some(address)) => {
+                self.watchpoints.insert(address as usize);
+                writeln!(out, "watchpoint on [{}]", address).unwrap();
+            }
+            ("u", Some(address)) | ("unwatch", Some(address)) => {
+                self.watchpoints.remove(&(address as usize));
+            }
+            ("l", _) | ("list", _) => {
+                let mut address = self.program.ip();
+                for _ in 0..address_or(&args, 0, 5) {
+                    let (text, len) = describe(&self.program, address);
+                    let marker = if self.breakpoints.contains(&address) {
+                        '*'
+                    } else {
+                        ' '
+                    };
+                    writeln!(out, "{}{:>5}: {}", marker, address, text).unwrap();
+                    address += len;
+                }
+            }
+            ("r", _) | ("regs", _) => {
+                writeln!(
+                    out,
+                    "ip {} relative base {} input {:?} output {:?}",
+                    self.program.ip(),
+                    self.program.relative_base(),
+                    self.program.input(),
+                    self.program.output()
+                )
+                .unwrap();
+            }
+            ("x", Some(address)) | ("dump", Some(address)) => {
+                let address = address as usize;
+                let cells: Vec<String> = (address..address + address_or(&args, 1, 10))
+                    .map(|address| self.program.get(address).to_string())
+                    .collect();
+                writeln!(out, "{:>5}: {}", address, cells.join(" ")).unwrap();
+            }
+            ("p", Some(address)) | ("patch", Some(address)) => match args.get(1) {
+                Some(Some(value)) => {
+                    self.program.set(address as usize, *value);
+                    writeln!(out, "[{}] = {}", address, value).unwrap();
+                }
+                _ => out.push_str("usage: patch <addr> <value>\n"),
+            },
+            ("i", _) | ("input", _) => {
+                let values = words[1..].join("");
+                for value in values.split(',').filter(|value| !value.is_empty()) {
+                    match value.parse() {
+                        Ok(value) => self.program.push_input(value),
+                        Err(_) => writeln!(out, "not a number: {}", value).unwrap(),
+                    }
+                }
+            }
+            _ => writeln!(out, "unknown command or missing address: {}", line.trim()).unwrap(),
+        }
+
+        Some(out)
+    }
+
+    /// Runs until something worth stopping for happens. The instruction the program is
+    /// on is always executed, so continuing from a breakpoint moves past it.
+    fn resume(&mut self, out: &mut String) {
+        loop {
+            if let Flow::Stop = self.step(out, false) {
+                return;
+            }
+            let ip = self.program.ip();
+            if self.breakpoints.contains(&ip) {
+                writeln!(out, "breakpoint at {}", ip).unwrap();
+                return;
+            }
+        }
+    }
+
+    /// Like `resume`, but backwards through the journal.
+    fn reverse(&mut self, out: &mut String) {
+        loop {
+            if let Flow::Stop = self.step_back(out) {
+                return;
+            }
+            let ip = self.program.ip();
+            if self.breakpoints.contains(&ip) {
+                writeln!(out, "breakpoint at {}", ip).unwrap();
+                return;
+            }
+        }
+    }
+
+    fn step_back(&mut self, out: &mut String) -> Flow {
+        let watched: Vec<(usize, i64)> = self
+            .watchpoints
+            .iter()
+            .map(|address| (*address, self.program.get(*address)))
+            .collect();
+
+        if !self.program.step_back() {
+            writeln!(out, "at start of history").unwrap();
+            return Flow::Stop;
+        }
+
+        let mut flow = Flow::Continue;
+        for (address, new) in watched {
+            let old = self.program.get(address);
+            if old != new {
+                writeln!(
+                    out,
+                    "watchpoint [{}]: {} <- {} by ip {}",
+                    address,
+                    old,
+                    new,
+                    self.program.ip()
+                )
+                .unwrap();
+                flow = Flow::Stop;
+            }
+        }
+        flow
+    }
+
+    fn step(&mut self, out: &mut String, verbose: bool) -> Flow {
+        let watched: Vec<(usize, i64)> = self
+            .watchpoints
+            .iter()
+            .map(|address| (*address, self.program.get(*address)))
+            .collect();
+
+        let mut flow = match self.program.step() {
+            Ok(None) => Flow::Continue,
+            Ok(Some(State::Output(value))) => {
+                writeln!(out, "output: {}", value).unwrap();
+                Flow::Continue
+            }
+            Ok(Some(State::NeedsInput)) => {
+                writeln!(out, "waiting for input at {}", self.program.ip()).unwrap();
+                Flow::Stop
+            }
+            Ok(Some(State::Halted)) => {
+                writeln!(out, "halted at {}", self.program.ip()).unwrap();
+                Flow::Stop
+            }
+            Err(error) => {
+                writeln!(out, "error: {}", error).unwrap();
+                Flow::Stop
+            }
+        };
+
+        for event in self.events.try_iter() {
+            if verbose {
+                writeln!(out, "{}", event.to_text()).unwrap();
+            }
+            for (address, value) in event.writes {
+                if let Some((_, old)) = watched.iter().find(|(watched, _)| *watched == address) {
+                    writeln!(
+                        out,
+                        "watchpoint [{}]: {} -> {} by ip {}",
+                        address, old, value, event.ip
+                    )
+                    .unwrap();
+                    flow = Flow::Stop;
+                }
+            }
+        }
+
+        flow
+    }
+}
+
+fn address_or(args: &[Option<i64>], idx: usize, default: usize) -> usize {
+    match args.get(idx) {
+        Some(Some(value)) if *value >= 0 => *value as usize,
+        _ => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debugger() -> Debugger {
+        // doubles the input until it is at least 100
+        Debugger::new(Program::from(
+            "3,20,1002,20,2,20,1007,20,100,21,1005,21,2,4,20,99,0,0,0,0,0,0",
+        ))
+    }
+
+    #[test]
+    fn test_step_and_regs() {
+        let mut debugger = debugger();
+        assert_eq!(
+            "waiting for input at 0\n",
+            debugger.command("step").unwrap()
+        );
+
+        debugger.command("input 3");
+        assert_eq!(
+            "ip 0: IN (3) modes [0] operands [] writes [20]=3\n",
+            debugger.command("s").unwrap()
+        );
+        assert_eq!(
+            "ip 2 relative base 0 input [] output []\n",
+            debugger.command("regs").unwrap()
+        );
+        assert_eq!("   20: 3 0 0\n", debugger.command("dump 20 3").unwrap());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = debugger();
+        debugger.command("input 3");
+        debugger.command("break 13");
+        assert_eq!("breakpoint at 13\n", debugger.command("continue").unwrap());
+        assert_eq!(192, debugger.program().get(20));
+
+        assert_eq!(
+            "output: 192\nhalted at 15\n",
+            debugger.command("c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut debugger = debugger();
+        debugger.command("i 30");
+        debugger.command("watch 20");
+        assert_eq!(
+            "watchpoint [20]: 0 -> 30 by ip 0\n",
+            debugger.command("c").unwrap()
+        );
+        assert_eq!(
+            "watchpoint [20]: 30 -> 60 by ip 2\n",
+            debugger.command("c").unwrap()
+        );
+        debugger.command("unwatch 20");
+        assert_eq!(
+            "output: 120\nhalted at 15\n",
+            debugger.command("c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_back_and_reverse() {
+        let mut debugger = debugger();
+        debugger.command("i 30");
+        debugger.command("c");
+        assert_eq!(120, debugger.program().get(20));
+        assert_eq!("   13: OUT [20]\n", debugger.command("back").unwrap());
+        assert!(debugger.program().output().is_empty());
+
+        debugger.command("watch 20");
+        assert_eq!(
+            "watchpoint [20]: 60 <- 120 by ip 2\n",
+            debugger.command("reverse").unwrap()
+        );
+        debugger.command("unwatch 20");
+        debugger.command("break 2");
+        assert_eq!("breakpoint at 2\n", debugger.command("v").unwrap());
+        assert_eq!(30, debugger.program().get(20));
+
+        assert_eq!(
+            "at start of history\n    0: IN -> [20]\n",
+            debugger.command("k 5").unwrap()
+        );
+        assert_eq!(
+            "ip 0 relative base 0 input [30] output []\n",
+            debugger.command("r").unwrap()
+        );
+        debugger.command("delete 2");
+        assert_eq!(
+            "output: 120\nhalted at 15\n",
+            debugger.command("c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_list_and_patch() {
+        let mut debugger = debugger();
+        debugger.command("b 2");
+        assert_eq!(
+            "     0: IN -> [20]\n*    2: MUL [20], #2 -> [20]\n",
+            debugger.command("list 2").unwrap()
+        );
+
+        assert_eq!("[1] = 21\n", debugger.command("patch 1 21").unwrap());
+        assert_eq!(21, debugger.program().get(1));
+        assert_eq!(None, debugger.command("quit"));
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut debugger = debugger();
+        let mut output = vec![];
+        debugger
+            .repl("input 60\ncontinue\nquit\nstep\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(
+            "(icdb) (icdb) output: 120\nhalted at 15\n(icdb) ",
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+use super::{ArgumentModes, IntcodeError, Mnemonic, Program};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// A decoded instruction together with the address it was found at.
+struct Instruction {
+    address: usize,
+    mnemonic: &'static Mnemonic,
+    modes: ArgumentModes,
+    args: Vec<i64>,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `address`, or `None` when the cell is no valid
+    /// instruction and should be shown as data.
+    fn decode(program: &Program, address: usize) -> Option<Instruction> {
+        let opcode = program.get(address);
+        if opcode < 0 {
+            return None;
+        }
+        let mnemonic = Mnemonic::find(opcode % 100)?;
+        let modes = ArgumentModes::new(opcode);
+
+        let valid_modes = !modes.has_extra_modes()
+            && modes
+                .modes
+                .iter()
+                .enumerate()
+                .all(|(arg, mode)| match mode {
+                    0 => true,
+                    1 => arg < mnemonic.reads,
+                    2 => arg < mnemonic.arity(),
+                    _ => false,
+                });
+        if !valid_modes {
+            return None;
+        }
+
+        let args = (0..mnemonic.arity())
+            .map(|arg| program.get(address + 1 + arg))
+            .collect();
+
+        Some(Instruction {
+            address,
+            mnemonic,
+            modes,
+            args,
+        })
+    }
+
+    fn len(&self) -> usize {
+        1 + self.args.len()
+    }
+
+    fn operand(&self, arg: usize) -> String {
+        let value = self.args[arg];
+        match self.modes.get_mode(arg) {
+            1 => format!("#{}", value),
+            2 if value < 0 => format!("[rb{}]", value),
+            2 => format!("[rb+{}]", value),
+            _ => format!("[{}]", value),
+        }
+    }
+
+    /// Addresses execution can continue at after this instruction, as far as they can be
+    /// known without running the program.
+    fn successors(&self) -> Vec<usize> {
+        let next = self.address + self.len();
+        match self.mnemonic.opcode {
+            99 => vec![],
+            5 | 6 => {
+                let mut successors = vec![];
+                let condition = match self.modes.get_mode(0) {
+                    1 => Some(self.args[0] != 0),
+                    _ => None,
+                };
+                let jumps_when = self.mnemonic.opcode == 5;
+                if condition != Some(!jumps_when)
+                    && self.modes.get_mode(1) == 1
+                    && self.args[1] >= 0
+                {
+                    successors.push(self.args[1] as usize);
+                }
+                if condition != Some(jumps_when) {
+                    successors.push(next);
+                }
+                successors
+            }
+            _ => vec![next],
+        }
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.mnemonic.name)?;
+        let operands: Vec<String> = (0..self.mnemonic.reads)
+            .map(|arg| self.operand(arg))
+            .collect();
+        if !operands.is_empty() {
+            write!(f, " {}", operands.join(", "))?;
+        }
+        if self.mnemonic.writes {
+            write!(f, " -> {}", self.operand(self.mnemonic.reads))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the single instruction at `address`, or `DATA` when it does not decode.
+pub(crate) fn describe(program: &Program, address: usize) -> (String, usize) {
+    match Instruction::decode(program, address) {
+        Some(instruction) => (instruction.to_string(), instruction.len()),
+        None => (format!("DATA {}", program.get(address)), 1),
+    }
+}
+
+/// Renders a program in the text format `Program::from` accepts as one mnemonic per
+/// line, e.g. `MUL [4], #3 -> [4]`. Operands are `[address]` for position mode, `#value`
+/// for immediate mode and `[rb+offset]` for relative mode. Cells that cannot be reached
+/// by following the control flow from address 0 are shown as `DATA`.
+pub fn disassemble(str: &str) -> Result<String, IntcodeError> {
+    let program = Program::new(str)?;
+
+    let mut instructions = vec![];
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![0];
+    while let Some(address) = pending.pop() {
+        if address >= program.len() || !visited.insert(address) {
+            continue;
+        }
+        if let Some(instruction) = Instruction::decode(&program, address) {
+            pending.extend(instruction.successors());
+            instructions.push(instruction);
+        }
+    }
+    instructions.sort_by_key(|instruction| instruction.address);
+
+    let mut output = String::new();
+    let mut address = 0;
+    let mut instructions = instructions.into_iter().peekable();
+    while address < program.len() {
+        // an instruction that starts inside the one just listed overlaps it
+        while instructions
+            .next_if(|instruction| instruction.address < address)
+            .is_some()
+        {}
+        match instructions.next_if(|instruction| instruction.address == address) {
+            Some(instruction) => {
+                writeln!(output, "{:>5}: {}", address, instruction).unwrap();
+                address += instruction.len();
+            }
+            None => {
+                writeln!(output, "{:>5}: DATA {}", address, program.get(address)).unwrap();
+                address += 1;
+            }
+        }
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(
+            "    0: MUL [4], #3 -> [4]\n    4: DATA 33\n",
+            disassemble("1002,4,3,4,33").unwrap()
+        );
+
+        assert_eq!(
+            "    0: IN -> [0]\n    2: OUT [0]\n    4: HALT\n",
+            disassemble("3,0,4,0,99").unwrap()
+        );
+
+        assert_eq!(
+            "    0: ARB #1\n    2: OUT [rb-1]\n    4: ADD [rb+3], #-7 -> [rb+0]\n    8: HALT\n",
+            disassemble("109,1,204,-1,21201,3,-7,0,99").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_disassemble_jumps() {
+        // the unconditional jump skips the cells in between, which become data
+        assert_eq!(
+            "    0: JNZ #1, #5\n    3: DATA 104\n    4: DATA 7\n    5: HALT\n",
+            disassemble("1105,1,5,104,7,99").unwrap()
+        );
+
+        // a conditional jump on a position argument can fall through
+        assert_eq!(
+            "    0: JZ [7], #6\n    3: OUT #7\n    5: HALT\n    6: HALT\n    7: DATA 0\n",
+            disassemble("1006,7,6,104,7,99,99,0").unwrap()
+        );
+
+        // the jump target 4 lies inside the output at 3, which is listed instead
+        assert_eq!(
+            "    0: JNZ [20], #4\n    3: OUT #99\n    5: OUT #1\n    7: HALT\n",
+            disassemble("1005,20,4,104,99,104,1,99").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_disassemble_invalid() {
+        assert_eq!(
+            "    0: DATA 11101\n    1: DATA 1\n    2: DATA 1\n    3: DATA 0\n",
+            disassemble("11101,1,1,0").unwrap()
+        );
+        assert!(disassemble("1,x").is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use super::{IntcodeError, Program, State};
+use crate::grid::{bounds, Directions, Pos};
+use std::collections::{HashMap, VecDeque};
+
+/// What a repair droid found at a position of the maze.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Cell {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+/// The map a repair droid program explored, with the droid's start at `Pos::start()`.
+///
+/// The droid is driven with movement commands 1 to 4 for north, south, west and east
+/// and replies 0 when it hit a wall, 1 when it moved and 2 when it moved onto the
+/// oxygen system.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Maze {
+    cells: HashMap<Pos, Cell>,
+}
+
+impl Maze {
+    /// Maps every reachable position with a depth-first search. Each branch continues
+    /// on its own copy of the droid's program, so the droid never has to walk back.
+    pub fn explore(program: &Program) -> Result<Maze, IntcodeError> {
+        let mut cells = HashMap::new();
+        cells.insert(Pos::start(), Cell::Open);
+        let mut stack = vec![(Pos::start(), program.clone())];
+
+        while let Some((pos, droid)) = stack.pop() {
+            for direction in Directions::ALL.iter() {
+                let next = pos.step(*direction);
+                if cells.contains_key(&next) {
+                    continue;
+                }
+
+                let mut droid = droid.clone();
+                droid.push_input(command(*direction));
+                let cell = match droid.run()? {
+                    State::Output(0) => Cell::Wall,
+                    State::Output(1) => Cell::Open,
+                    State::Output(2) => Cell::Oxygen,
+                    State::Output(value) => {
+                        return Err(IntcodeError::UnexpectedOutput {
+                            ip: droid.instruction,
+                            value,
+                        })
+                    }
+                    State::NeedsInput => {
+                        return Err(IntcodeError::InputRequired { ip: droid.ip() })
+                    }
+                    State::Halted => return Err(IntcodeError::UnexpectedHalt { ip: droid.ip() }),
+                };
+                cells.insert(next.clone(), cell);
+                if cell != Cell::Wall {
+                    stack.push((next, droid));
+                }
+            }
+        }
+
+        Ok(Maze { cells })
+    }
+
+    /// What is at `pos`, or `None` if the droid never saw it.
+    pub fn cell(&self, pos: &Pos) -> Option<Cell> {
+        self.cells.get(pos).copied()
+    }
+
+    /// Where the oxygen system is, if the droid found it.
+    pub fn oxygen(&self) -> Option<Pos> {
+        self.cells
+            .iter()
+            .find(|(_, cell)| **cell == Cell::Oxygen)
+            .map(|(pos, _)| pos.clone())
+    }
+
+    /// The number of moves from `from` to every position reachable from it, found
+    /// with a breadth-first search.
+    pub fn distances(&self, from: &Pos) -> HashMap<Pos, usize> {
+        let mut distances = HashMap::new();
+        if !self.is_open(from) {
+            return distances;
+        }
+        distances.insert(from.clone(), 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from.clone());
+
+        while let Some(pos) = queue.pop_front() {
+            let distance = distances[&pos];
+            for direction in Directions::ALL.iter() {
+                let next = pos.step(*direction);
+                if self.is_open(&next) && !distances.contains_key(&next) {
+                    distances.insert(next.clone(), distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// The fewest moves from `from` to `to`, or `None` if there is no way.
+    pub fn shortest_path(&self, from: &Pos, to: &Pos) -> Option<usize> {
+        self.distances(from).get(to).copied()
+    }
+
+    /// Minutes until oxygen spreading from the oxygen system, one step per minute,
+    /// fills every open position it can reach.
+    pub fn fill_time(&self) -> Option<usize> {
+        let oxygen = self.oxygen()?;
+        self.distances(&oxygen).values().max().copied()
+    }
+
+    /// Draws the map, north at the top, with `#` for walls, `.` for open positions,
+    /// `O` for the oxygen system and a space where the droid never looked.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let (min, max) = match bounds(self.cells.keys()) {
+            Some(bounds) => bounds,
+            None => return text,
+        };
+        for y in (min.y..=max.y).rev() {
+            for x in min.x..=max.x {
+                text.push(match self.cell(&Pos { x, y }) {
+                    Some(Cell::Wall) => '#',
+                    Some(Cell::Open) => '.',
+                    Some(Cell::Oxygen) => 'O',
+                    None => ' ',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    fn is_open(&self, pos: &Pos) -> bool {
+        matches!(self.cell(pos), Some(Cell::Open) | Some(Cell::Oxygen))
+    }
+}
+
+/// The movement command that moves the droid in `direction`, with north up.
+fn command(direction: Directions) -> i64 {
+    match direction {
+        Directions::U => 1,
+        Directions::D => 2,
+        Directions::L => 3,
+        Directions::R => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+
+    // a droid in a 6 by 5 maze kept in memory row by row, with the relative base
+    // pointing at the droid's cell
+    const DROID: &str = "
+                ARB #start
+        loop:   IN -> [command]
+                EQ [command], #1 -> [test]
+                JNZ [test], #north
+                EQ [command], #2 -> [test]
+                JNZ [test], #south
+                EQ [command], #3 -> [test]
+                JNZ [test], #west
+                OUT [rb+1]
+                JZ [rb+1], #loop
+                ARB #1
+                JNZ #1, #loop
+        north:  OUT [rb-6]
+                JZ [rb-6], #loop
+                ARB #-6
+                JNZ #1, #loop
+        south:  OUT [rb+6]
+                JZ [rb+6], #loop
+                ARB #6
+                JNZ #1, #loop
+        west:   OUT [rb-1]
+                JZ [rb-1], #loop
+                ARB #-1
+                JNZ #1, #loop
+        command: DATA 0
+        test:   DATA 0
+                DATA 0, 0, 0, 0, 0, 0
+                DATA 0, 1, 1, 0, 0, 0
+                DATA 0, 1, 0, 1
+        start:  DATA 1
+                DATA 0
+                DATA 0, 1, 2, 1, 0, 0
+                DATA 0, 0, 0, 0, 0, 0
+    ";
+
+    fn maze() -> Maze {
+        Maze::explore(&Program::from(assemble(DROID).unwrap().as_str())).unwrap()
+    }
+
+    #[test]
+    fn test_explore() {
+        let maze = maze();
+        assert_eq!(" ##   \n#..## \n#.#..#\n#.O.# \n ###  \n", maze.render());
+        assert_eq!(Some(Cell::Open), maze.cell(&Pos::start()));
+        assert_eq!(Some(Pos { x: -2, y: -1 }), maze.oxygen());
+    }
+
+    #[test]
+    fn test_paths() {
+        let maze = maze();
+        let oxygen = maze.oxygen().unwrap();
+        assert_eq!(Some(3), maze.shortest_path(&Pos::start(), &oxygen));
+        assert_eq!(Some(4), maze.fill_time());
+        assert_eq!(None, maze.shortest_path(&Pos::start(), &Pos { x: 1, y: 0 }));
+        assert_eq!(8, maze.distances(&Pos::start()).len());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err(IntcodeError::UnexpectedHalt { ip: 2 }),
+            Maze::explore(&Program::from("3,0,99"))
+        );
+        assert_eq!(
+            Err(IntcodeError::UnexpectedOutput { ip: 2, value: 3 }),
+            Maze::explore(&Program::from("3,0,104,3,99"))
+        );
+    }
+}
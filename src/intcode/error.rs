@@ -0,0 +1,93 @@
+use super::Limit;
+use std::error::Error;
+use std::fmt;
+
+/// A failure while loading or running a `Program`. `ip` is always the address of the
+/// instruction that failed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IntcodeError {
+    /// The value at `index` of the program or input text is not a number.
+    Parse { index: usize, value: String },
+    /// The opcode at `ip` is not one the VM knows.
+    UnknownOpcode { ip: usize, opcode: i64 },
+    /// An argument of `opcode` uses a mode that does not exist or is not valid there,
+    /// like immediate mode for a write.
+    InvalidMode { ip: usize, opcode: i64, mode: u32 },
+    /// An input instruction ran with nothing left in the input queue.
+    InputRequired { ip: usize },
+    /// The program halted where the caller still expected output.
+    UnexpectedHalt { ip: usize },
+    /// An arithmetic instruction produced a value that does not fit the word type.
+    Overflow { ip: usize },
+    /// An argument resolved to an address below zero.
+    NegativeAddress { ip: usize, address: i64 },
+    /// A value used as opcode, address or offset does not fit in an `i64`.
+    ValueOutOfRange { ip: usize, value: String },
+    /// A program driven by a harness output `value`, which its protocol does not allow
+    /// there.
+    UnexpectedOutput { ip: usize, value: i64 },
+    /// A network of programs went idle, with every machine waiting for input, before
+    /// it produced what was asked for.
+    NetworkIdle,
+    /// Line `line` of a saved snapshot is malformed, or a field is missing.
+    InvalidSnapshot { line: usize },
+    /// Execution was stopped by a step limit or timeout before the instruction at `ip`.
+    LimitExceeded { ip: usize, limit: Limit },
+    /// An instruction could not be registered: its opcode is not between 1 and 99 or
+    /// it has more than three operands.
+    InvalidInstruction { opcode: i64 },
+    /// A registered instruction wrote although its `Mnemonic` has no write operand.
+    InvalidEffect { ip: usize, opcode: i64 },
+    /// Text sent to an ASCII program has a character that is not ASCII at byte `index`.
+    NotAscii { index: usize, char: char },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::Parse { index, value } => {
+                write!(f, "value {:?} at index {} is not a number", value, index)
+            }
+            IntcodeError::UnknownOpcode { ip, opcode } => {
+                write!(f, "unknown opcode {} at ip {}", opcode, ip)
+            }
+            IntcodeError::InvalidMode { ip, opcode, mode } => {
+                write!(f, "invalid mode {} in opcode {} at ip {}", mode, opcode, ip)
+            }
+            IntcodeError::InputRequired { ip } => write!(f, "input required at ip {}", ip),
+            IntcodeError::UnexpectedHalt { ip } => write!(f, "unexpected halt at ip {}", ip),
+            IntcodeError::Overflow { ip } => write!(f, "arithmetic overflow at ip {}", ip),
+            IntcodeError::NegativeAddress { ip, address } => {
+                write!(f, "negative memory address {} at ip {}", address, ip)
+            }
+            IntcodeError::ValueOutOfRange { ip, value } => {
+                write!(f, "value {} out of range at ip {}", value, ip)
+            }
+            IntcodeError::UnexpectedOutput { ip, value } => {
+                write!(f, "unexpected output {} at ip {}", value, ip)
+            }
+            IntcodeError::NetworkIdle => write!(f, "network went idle"),
+            IntcodeError::InvalidSnapshot { line } => {
+                write!(f, "invalid snapshot at line {}", line)
+            }
+            IntcodeError::LimitExceeded { ip, limit } => {
+                write!(f, "{} exceeded at ip {}", limit, ip)
+            }
+            IntcodeError::InvalidInstruction { opcode } => {
+                write!(f, "cannot register an instruction with opcode {}", opcode)
+            }
+            IntcodeError::InvalidEffect { ip, opcode } => {
+                write!(
+                    f,
+                    "opcode {} at ip {} wrote without a write operand",
+                    opcode, ip
+                )
+            }
+            IntcodeError::NotAscii { index, char } => {
+                write!(f, "character {:?} at index {} is not ASCII", char, index)
+            }
+        }
+    }
+}
+
+impl Error for IntcodeError {}
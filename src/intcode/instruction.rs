@@ -0,0 +1,443 @@
+use super::{to_address, to_number, IntcodeError, Word};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+
+/// Opcode, name and operand layout of an instruction. Read operands may use any
+/// parameter mode; the write operand, which always comes last, only position and
+/// relative mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mnemonic {
+    pub opcode: i64,
+    pub name: &'static str,
+    /// Number of arguments the instruction reads.
+    pub reads: usize,
+    /// Whether the instruction has a trailing argument it writes to.
+    pub writes: bool,
+}
+
+#[rustfmt::skip]
+mod builtin {
+    use super::Mnemonic;
+
+    pub const ADD: Mnemonic = Mnemonic { opcode: 1, name: "ADD", reads: 2, writes: true };
+    pub const MUL: Mnemonic = Mnemonic { opcode: 2, name: "MUL", reads: 2, writes: true };
+    pub const IN: Mnemonic = Mnemonic { opcode: 3, name: "IN", reads: 0, writes: true };
+    pub const OUT: Mnemonic = Mnemonic { opcode: 4, name: "OUT", reads: 1, writes: false };
+    pub const JNZ: Mnemonic = Mnemonic { opcode: 5, name: "JNZ", reads: 2, writes: false };
+    pub const JZ: Mnemonic = Mnemonic { opcode: 6, name: "JZ", reads: 2, writes: false };
+    pub const LT: Mnemonic = Mnemonic { opcode: 7, name: "LT", reads: 2, writes: true };
+    pub const EQ: Mnemonic = Mnemonic { opcode: 8, name: "EQ", reads: 2, writes: true };
+    pub const ARB: Mnemonic = Mnemonic { opcode: 9, name: "ARB", reads: 1, writes: false };
+    pub const HALT: Mnemonic = Mnemonic { opcode: 99, name: "HALT", reads: 0, writes: false };
+}
+use builtin::*;
+
+/// The built-in instructions, shared by the disassembler and assembler.
+pub(crate) const MNEMONICS: [Mnemonic; 10] = [ADD, MUL, IN, OUT, JNZ, JZ, LT, EQ, ARB, HALT];
+
+impl Mnemonic {
+    /// Looks up a built-in instruction.
+    pub fn find(opcode: i64) -> Option<&'static Mnemonic> {
+        MNEMONICS.iter().find(|mnemonic| mnemonic.opcode == opcode)
+    }
+
+    pub fn arity(&self) -> usize {
+        self.reads + self.writes as usize
+    }
+}
+
+/// What executing an instruction does to the machine, applied by the VM after
+/// `Instruction::execute` returns.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect<W = i64> {
+    /// Go on with the next instruction.
+    Continue,
+    /// Store the value at the write operand.
+    Write(W),
+    /// Go on at the given address.
+    Jump(usize),
+    Output(W),
+    /// Add to the relative base.
+    AdjustRelativeBase(i64),
+    /// Wait for input; the instruction runs again once input is queued.
+    NeedsInput,
+    Halt,
+}
+
+/// The operands of an instruction being executed and the machine state it may use.
+pub struct Context<'a, W> {
+    pub(crate) ip: usize,
+    pub(crate) relative_base: i64,
+    pub(crate) operands: &'a [W],
+    pub(crate) input: &'a mut VecDeque<W>,
+    pub(crate) consumed: Option<W>,
+}
+
+impl<'a, W: Word> Context<'a, W> {
+    /// Address of the instruction.
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    /// Value of read operand `arg`, with its parameter mode applied.
+    pub fn operand(&self, arg: usize) -> &W {
+        &self.operands[arg]
+    }
+
+    /// Takes the next input value, if any is queued.
+    pub fn input(&mut self) -> Option<W> {
+        let value = self.input.pop_front()?;
+        self.consumed = Some(value.clone());
+        Some(value)
+    }
+
+    /// Converts a value to a number, failing with `ValueOutOfRange`.
+    pub fn number(&self, value: &W) -> Result<i64, IntcodeError> {
+        to_number(value, self.ip)
+    }
+
+    /// Converts a value to an address, failing for values out of range or below zero.
+    pub fn address(&self, value: &W) -> Result<usize, IntcodeError> {
+        to_address(self.number(value)?, self.ip)
+    }
+}
+
+/// An instruction the VM can execute. The built-in ones implement it as well, and
+/// `Program::register` adds new ones or replaces existing ones.
+///
+/// Read operands are resolved before `execute` is called, according to their
+/// parameter modes, and the effect it returns is applied afterwards, so an
+/// instruction never touches memory itself.
+pub trait Instruction<W: Word = i64>: Send + Sync {
+    fn mnemonic(&self) -> &Mnemonic;
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError>;
+}
+
+/// The instructions of a `Program` by opcode.
+#[derive(Clone)]
+pub(crate) struct InstructionSet<W> {
+    table: Vec<Option<Arc<dyn Instruction<W>>>>,
+    custom: bool,
+}
+
+impl<W: Word> InstructionSet<W> {
+    pub fn get(&self, opcode: i64) -> Option<&dyn Instruction<W>> {
+        if opcode < 0 {
+            return None;
+        }
+        self.table.get(opcode as usize)?.as_deref()
+    }
+
+    /// Adds a custom instruction.
+    pub fn register(&mut self, instruction: Arc<dyn Instruction<W>>) -> Result<(), IntcodeError> {
+        self.insert(instruction)?;
+        self.custom = true;
+        Ok(())
+    }
+
+    fn insert(&mut self, instruction: Arc<dyn Instruction<W>>) -> Result<(), IntcodeError> {
+        let opcode = instruction.mnemonic().opcode;
+        if !(1..100).contains(&opcode) || instruction.mnemonic().arity() > 3 {
+            return Err(IntcodeError::InvalidInstruction { opcode });
+        }
+        self.table[opcode as usize] = Some(instruction);
+        Ok(())
+    }
+
+    /// Whether any instruction was registered besides the built-in ones.
+    pub fn is_custom(&self) -> bool {
+        self.custom
+    }
+}
+
+impl<W: Word> Default for InstructionSet<W> {
+    fn default() -> Self {
+        let builtin: [Arc<dyn Instruction<W>>; 10] = [
+            Arc::new(Addition),
+            Arc::new(Multiplication),
+            Arc::new(Input),
+            Arc::new(Output),
+            Arc::new(Jump(true)),
+            Arc::new(Jump(false)),
+            Arc::new(Compare(Comparison::LessThan)),
+            Arc::new(Compare(Comparison::Equals)),
+            Arc::new(RelativeBase),
+            Arc::new(Halt),
+        ];
+
+        let mut set = InstructionSet {
+            table: vec![None; 100],
+            custom: false,
+        };
+        for instruction in builtin.iter() {
+            set.insert(instruction.clone()).unwrap();
+        }
+        set
+    }
+}
+
+impl<W> fmt::Debug for InstructionSet<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "InstructionSet {{ custom: {} }}", self.custom)
+    }
+}
+
+struct Addition;
+
+struct Multiplication;
+
+struct Input;
+
+struct Output;
+
+/// Jumps when the first operand is non-zero for `Jump(true)`, zero for `Jump(false)`.
+struct Jump(bool);
+
+enum Comparison {
+    LessThan,
+    Equals,
+}
+
+struct Compare(Comparison);
+
+struct RelativeBase;
+
+struct Halt;
+
+impl<W: Word> Instruction<W> for Addition {
+    fn mnemonic(&self) -> &Mnemonic {
+        &ADD
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        let value = context.operand(0).checked_add(context.operand(1));
+        value
+            .map(Effect::Write)
+            .ok_or(IntcodeError::Overflow { ip: context.ip() })
+    }
+}
+
+impl<W: Word> Instruction<W> for Multiplication {
+    fn mnemonic(&self) -> &Mnemonic {
+        &MUL
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        let value = context.operand(0).checked_mul(context.operand(1));
+        value
+            .map(Effect::Write)
+            .ok_or(IntcodeError::Overflow { ip: context.ip() })
+    }
+}
+
+impl<W: Word> Instruction<W> for Input {
+    fn mnemonic(&self) -> &Mnemonic {
+        &IN
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        Ok(context.input().map_or(Effect::NeedsInput, Effect::Write))
+    }
+}
+
+impl<W: Word> Instruction<W> for Output {
+    fn mnemonic(&self) -> &Mnemonic {
+        &OUT
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        Ok(Effect::Output(context.operand(0).clone()))
+    }
+}
+
+impl<W: Word> Instruction<W> for Jump {
+    fn mnemonic(&self) -> &Mnemonic {
+        if self.0 {
+            &JNZ
+        } else {
+            &JZ
+        }
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        if self.0 == context.operand(0).is_zero() {
+            return Ok(Effect::Continue);
+        }
+        Ok(Effect::Jump(context.address(context.operand(1))?))
+    }
+}
+
+macro_rules! ternary {
+    ($c:expr, $v:expr, $v1:expr) => {
+        if $c {
+            $v
+        } else {
+            $v1
+        }
+    };
+}
+
+impl<W: Word> Instruction<W> for Compare {
+    fn mnemonic(&self) -> &Mnemonic {
+        match self.0 {
+            Comparison::LessThan => &LT,
+            Comparison::Equals => &EQ,
+        }
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        let (a, b) = (context.operand(0), context.operand(1));
+        let value = match self.0 {
+            Comparison::LessThan => ternary!(a < b, 1, 0),
+            Comparison::Equals => ternary!(a == b, 1, 0),
+        };
+        Ok(Effect::Write(W::from_i64(value)))
+    }
+}
+
+impl<W: Word> Instruction<W> for RelativeBase {
+    fn mnemonic(&self) -> &Mnemonic {
+        &ARB
+    }
+
+    fn execute(&self, context: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        Ok(Effect::AdjustRelativeBase(
+            context.number(context.operand(0))?,
+        ))
+    }
+}
+
+impl<W: Word> Instruction<W> for Halt {
+    fn mnemonic(&self) -> &Mnemonic {
+        &HALT
+    }
+
+    fn execute(&self, _: &mut Context<W>) -> Result<Effect<W>, IntcodeError> {
+        Ok(Effect::Halt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Program;
+    use super::*;
+
+    /// `REV a -> b` stores its operand with the digits reversed.
+    struct Reverse;
+
+    impl Instruction for Reverse {
+        fn mnemonic(&self) -> &Mnemonic {
+            &Mnemonic {
+                opcode: 10,
+                name: "REV",
+                reads: 1,
+                writes: true,
+            }
+        }
+
+        fn execute(&self, context: &mut Context<i64>) -> Result<Effect<i64>, IntcodeError> {
+            let digits: String = context.operand(0).to_string().chars().rev().collect();
+            Ok(Effect::Write(digits.parse().unwrap()))
+        }
+    }
+
+    /// Outputs twice its operand instead of the operand itself.
+    struct Double;
+
+    impl Instruction for Double {
+        fn mnemonic(&self) -> &Mnemonic {
+            &OUT
+        }
+
+        fn execute(&self, context: &mut Context<i64>) -> Result<Effect<i64>, IntcodeError> {
+            Ok(Effect::Output(context.operand(0) * 2))
+        }
+    }
+
+    #[test]
+    fn test_builtin_metadata() {
+        let set: InstructionSet<i64> = InstructionSet::default();
+        for mnemonic in MNEMONICS.iter() {
+            assert_eq!(mnemonic, set.get(mnemonic.opcode).unwrap().mnemonic());
+        }
+        assert!(set.get(10).is_none());
+        assert!(set.get(-1).is_none());
+        assert!(!set.is_custom());
+    }
+
+    #[test]
+    fn test_register() {
+        let mut prog = Program::from("110,123,6,4,6,99,0");
+        assert_eq!(
+            Err(IntcodeError::UnknownOpcode { ip: 0, opcode: 110 }),
+            prog.clone().run()
+        );
+
+        prog.register(Reverse).unwrap();
+        assert_eq!("321\n", prog.execute("").unwrap());
+
+        let mut prog = Program::from("104,21,99");
+        prog.register(Double).unwrap();
+        assert_eq!("42\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_register() {
+        struct Long;
+
+        impl Instruction for Long {
+            fn mnemonic(&self) -> &Mnemonic {
+                &Mnemonic {
+                    opcode: 100,
+                    name: "LONG",
+                    reads: 1,
+                    writes: false,
+                }
+            }
+
+            fn execute(&self, _: &mut Context<i64>) -> Result<Effect<i64>, IntcodeError> {
+                Ok(Effect::Continue)
+            }
+        }
+
+        let mut prog = Program::from("99");
+        assert_eq!(
+            Err(IntcodeError::InvalidInstruction { opcode: 100 }),
+            prog.register(Long)
+        );
+    }
+
+    #[test]
+    fn test_write_without_operand() {
+        struct Five;
+
+        impl Instruction for Five {
+            fn mnemonic(&self) -> &Mnemonic {
+                &Mnemonic {
+                    opcode: 10,
+                    name: "FIVE",
+                    reads: 0,
+                    writes: false,
+                }
+            }
+
+            fn execute(&self, _: &mut Context<i64>) -> Result<Effect<i64>, IntcodeError> {
+                Ok(Effect::Write(5))
+            }
+        }
+
+        let mut prog = Program::from("10,99");
+        prog.register(Five).unwrap();
+        assert_eq!(
+            Err(IntcodeError::InvalidEffect { ip: 0, opcode: 10 }),
+            prog.run()
+        );
+        assert_eq!(0, prog.ip());
+    }
+}
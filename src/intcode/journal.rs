@@ -0,0 +1,77 @@
+use super::Word;
+
+/// Everything one instruction changed, enough to undo it.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry<W> {
+    pub ip: usize,
+    pub relative_base: i64,
+    /// Addresses written to with the value they held before, in write order.
+    pub writes: Vec<(usize, W)>,
+    pub input: Option<W>,
+    pub output: bool,
+}
+
+/// Undo log of executed instructions, used by `Program::step_back`.
+#[derive(Clone, Debug)]
+pub(crate) struct Journal<W> {
+    entries: Vec<Entry<W>>,
+    current: Option<Entry<W>>,
+}
+
+impl<W: Word> Journal<W> {
+    pub fn new() -> Journal<W> {
+        Journal {
+            entries: vec![],
+            current: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn begin(&mut self, ip: usize, relative_base: i64) {
+        self.current = Some(Entry {
+            ip,
+            relative_base,
+            writes: vec![],
+            input: None,
+            output: false,
+        });
+    }
+
+    pub fn write(&mut self, address: usize, old: &W) {
+        if let Some(entry) = self.current.as_mut() {
+            entry.writes.push((address, old.clone()));
+        }
+    }
+
+    pub fn input(&mut self, value: &W) {
+        if let Some(entry) = self.current.as_mut() {
+            entry.input = Some(value.clone());
+        }
+    }
+
+    pub fn output(&mut self) {
+        if let Some(entry) = self.current.as_mut() {
+            entry.output = true;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(entry) = self.current.take() {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Drops the instruction being recorded, for instructions that change nothing.
+    pub fn discard(&mut self) {
+        self.current = None;
+    }
+
+    /// Removes the most recent entry, dropping any half recorded instruction.
+    pub fn undo(&mut self) -> Option<Entry<W>> {
+        self.current = None;
+        self.entries.pop()
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How many instructions run between two looks at the clock.
+const CLOCK_INTERVAL: u64 = 1024;
+
+/// The execution limit that stopped a program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Limit {
+    Steps(u64),
+    Timeout(Duration),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::Steps(steps) => write!(f, "step limit of {}", steps),
+            Limit::Timeout(timeout) => write!(f, "timeout of {:?}", timeout),
+        }
+    }
+}
+
+/// The limits set on a `Program`. The timeout starts counting at the first
+/// instruction executed after it is set.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limits {
+    max_steps: Option<u64>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    ticks: u64,
+}
+
+impl Limits {
+    pub fn set_max_steps(&mut self, steps: u64) {
+        self.max_steps = Some(steps);
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+        self.deadline = None;
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.max_steps.is_some() || self.timeout.is_some()
+    }
+
+    /// Checks whether a program that already executed `steps` instructions may
+    /// execute another one.
+    pub fn check(&mut self, steps: u64) -> Result<(), Limit> {
+        if let Some(max_steps) = self.max_steps {
+            if steps >= max_steps {
+                return Err(Limit::Steps(max_steps));
+            }
+        }
+
+        if let Some(timeout) = self.timeout {
+            let deadline = *self
+                .deadline
+                .get_or_insert_with(|| Instant::now() + timeout);
+            self.ticks += 1;
+            if self.ticks.is_multiple_of(CLOCK_INTERVAL) && Instant::now() >= deadline {
+                return Err(Limit::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use super::Word;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const PAGE_SIZE: usize = 1024;
+
+/// Room past the image kept in the flat `Vec` for the scratch memory most programs
+/// use right after their code.
+const HEADROOM: usize = PAGE_SIZE;
+
+/// Unbounded program memory where every address reads as 0 until it is written.
+///
+/// The loaded program and writes up to `HEADROOM` past it live in one contiguous
+/// `Vec` that grows as needed. Writes further out go to fixed-size pages that are
+/// only allocated once something is stored in them.
+///
+/// The cells and the pages are shared copy-on-write, so cloning memory is cheap and
+/// a clone only copies the parts it writes to.
+#[derive(Clone, Debug)]
+pub struct Memory<W: Word> {
+    cells: Arc<Vec<W>>,
+    image_len: usize,
+    /// End of the flat region, a multiple of `PAGE_SIZE`.
+    flat_len: usize,
+    pages: HashMap<usize, Arc<Vec<W>>>,
+    zero: W,
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new(image: Vec<W>) -> Memory<W> {
+        Memory {
+            image_len: image.len(),
+            flat_len: (image.len() + HEADROOM).div_ceil(PAGE_SIZE) * PAGE_SIZE,
+            cells: Arc::new(image),
+            pages: HashMap::new(),
+            zero: W::from_i64(0),
+        }
+    }
+
+    /// Stores `values` from `address` onwards, as laid out by `pages`.
+    pub fn load(&mut self, address: usize, values: Vec<W>) {
+        for (offset, value) in values.into_iter().enumerate() {
+            self.set(address + offset, value);
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, address: usize) -> &W {
+        if let Some(value) = self.cells.get(address) {
+            return value;
+        }
+        if address < self.flat_len {
+            return &self.zero;
+        }
+
+        self.pages
+            .get(&(address / PAGE_SIZE))
+            .map(|page| &page[address % PAGE_SIZE])
+            .unwrap_or(&self.zero)
+    }
+
+    #[inline]
+    pub fn set(&mut self, address: usize, value: W) {
+        if address < self.flat_len {
+            let cells = Arc::make_mut(&mut self.cells);
+            if address >= cells.len() {
+                let len = (address / PAGE_SIZE + 1) * PAGE_SIZE;
+                cells.resize(len, self.zero.clone());
+            }
+            cells[address] = value;
+            return;
+        }
+
+        let zero = &self.zero;
+        let page = self
+            .pages
+            .entry(address / PAGE_SIZE)
+            .or_insert_with(|| Arc::new(vec![zero.clone(); PAGE_SIZE]));
+        Arc::make_mut(page)[address % PAGE_SIZE] = value;
+    }
+
+    /// The loaded image.
+    pub fn image(&self) -> &[W] {
+        &self.cells[..self.image_len]
+    }
+
+    /// Memory past the image that has been written to, as start addresses and
+    /// contents in address order.
+    pub fn pages(&self) -> Vec<(usize, &[W])> {
+        let mut pages: Vec<(usize, &[W])> = self
+            .pages
+            .iter()
+            .map(|(page, values)| (page * PAGE_SIZE, values.as_slice()))
+            .collect();
+        if self.cells.len() > self.image_len {
+            pages.push((self.image_len, &self.cells[self.image_len..]));
+        }
+        pages.sort_by_key(|(address, _)| *address);
+        pages
+    }
+
+    /// Size of the loaded program image, not counting any memory written past it.
+    pub fn len(&self) -> usize {
+        self.image_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.image_len == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_default_to_zero() {
+        let memory: Memory<i64> = Memory::new(vec![1, 2, 3]);
+        assert_eq!(3, *memory.get(2));
+        assert_eq!(0, *memory.get(3));
+        assert_eq!(0, *memory.get(usize::MAX));
+        assert!(memory.pages.is_empty());
+    }
+
+    #[test]
+    fn test_paged_writes() {
+        let mut memory: Memory<i64> = Memory::new(vec![1, 2, 3]);
+        memory.set(1, 20);
+        memory.set(5000, 7);
+        memory.set(5001, 8);
+        memory.set(1_000_000, 9);
+
+        assert_eq!(20, *memory.get(1));
+        assert_eq!(7, *memory.get(5000));
+        assert_eq!(8, *memory.get(5001));
+        assert_eq!(0, *memory.get(5002));
+        assert_eq!(9, *memory.get(1_000_000));
+        assert_eq!(3, memory.cells.len());
+        assert_eq!(2, memory.pages.len());
+        assert_eq!(3, memory.len());
+
+        memory.set(1000, 6);
+        assert_eq!(6, *memory.get(1000));
+        assert_eq!(1024, memory.cells.len());
+
+        memory.set(1 << 40, 10);
+        memory.set((1 << 40) + 1, 11);
+        assert_eq!(10, *memory.get(1 << 40));
+        assert_eq!(11, *memory.get((1 << 40) + 1));
+        assert_eq!(0, *memory.get((1 << 40) + 2));
+        assert_eq!(3, memory.pages.len());
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut memory: Memory<i64> = Memory::new(vec![1, 2, 3]);
+        memory.set(5000, 7);
+
+        let mut copy = memory.clone();
+        assert!(Arc::ptr_eq(&memory.cells, &copy.cells));
+        copy.set(0, 10);
+        copy.set(4, 9);
+        copy.set(5001, 8);
+        assert!(!Arc::ptr_eq(&memory.cells, &copy.cells));
+
+        assert_eq!(1, *memory.get(0));
+        assert_eq!(0, *memory.get(5001));
+        assert_eq!(10, *copy.get(0));
+        assert_eq!(7, *copy.get(5000));
+        assert_eq!(
+            vec![3, 4096],
+            copy.pages()
+                .iter()
+                .map(|(address, _)| *address)
+                .collect::<Vec<_>>()
+        );
+    }
+}
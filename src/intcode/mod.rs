@@ -0,0 +1,1093 @@
+pub mod amplifier;
+pub mod arcade;
+pub mod ascii;
+pub mod assembler;
+pub mod compiler;
+pub mod debugger;
+pub mod disassembler;
+pub mod droid;
+mod error;
+mod instruction;
+mod journal;
+mod limits;
+mod memory;
+pub mod network;
+pub mod profiler;
+pub mod robot;
+mod selfmod;
+mod snapshot;
+pub mod threaded;
+pub mod trace;
+mod word;
+
+pub use error::IntcodeError;
+use instruction::InstructionSet;
+pub use instruction::{Context, Effect, Instruction, Mnemonic};
+use journal::Journal;
+pub use limits::Limit;
+use limits::Limits;
+use memory::Memory;
+use profiler::Profile;
+pub use selfmod::CodeWrite;
+use selfmod::SelfModification;
+pub use snapshot::Snapshot;
+use trace::{Trace, Tracer};
+pub use word::Word;
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Why `Program::run` stopped; call `run` again to resume after an output or after
+/// feeding more input with `push_input`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum State<W = i64> {
+    NeedsInput,
+    Output(W),
+    Halted,
+}
+
+#[derive(Clone, Debug)]
+pub struct Program<W: Word = i64> {
+    memory: Memory<W>,
+    index: usize,
+    instruction: usize,
+    relative_base: i64,
+    input: VecDeque<W>,
+    output: Vec<W>,
+    /// Set once a halt instruction ran, so running again neither repeats nor counts it.
+    halted: bool,
+    trace: Trace<W>,
+    journal: Option<Journal<W>>,
+    code_writes: Option<SelfModification<W>>,
+    limits: Limits,
+    steps: u64,
+    /// Executed instructions by opcode.
+    counts: [u64; 100],
+    profile: Option<Profile>,
+    /// Decoded instructions of the loaded image by address, cleared on writes.
+    decoded: Vec<Option<ArgumentModes>>,
+    instructions: Arc<InstructionSet<W>>,
+    /// Operand buffer reused across instructions.
+    operands: Vec<W>,
+}
+
+impl Program {
+    pub fn new(str: &str) -> Result<Program, IntcodeError> {
+        Program::parse(str)
+    }
+}
+
+impl<W: Word> Program<W> {
+    /// Loads a program with a word type other than the default `i64`.
+    pub fn parse(str: &str) -> Result<Program<W>, IntcodeError> {
+        let image = str
+            .trim()
+            .split(',')
+            .enumerate()
+            .map(|(index, str)| parse_word(index, str))
+            .collect::<Result<Vec<W>, IntcodeError>>()?;
+
+        Ok(Program::with_memory(Memory::new(image)))
+    }
+
+    fn with_memory(memory: Memory<W>) -> Program<W> {
+        Program {
+            input: VecDeque::new(),
+            output: vec![],
+            memory,
+            index: 0,
+            instruction: 0,
+            relative_base: 0,
+            halted: false,
+            trace: Trace::new(),
+            journal: None,
+            code_writes: None,
+            limits: Limits::default(),
+            steps: 0,
+            counts: [0; 100],
+            profile: None,
+            decoded: vec![],
+            instructions: Arc::new(InstructionSet::default()),
+            operands: vec![],
+        }
+    }
+
+    /// Saves the machine state so it can be restored later, e.g. to rerun a program
+    /// from the same point with different inputs.
+    pub fn snapshot(&self) -> Snapshot<W> {
+        Snapshot::new(self)
+    }
+
+    /// Puts the machine back in the state of `snapshot`. The tracer stays installed and
+    /// the undo journal and code write tracking, if enabled, start over.
+    pub fn restore(&mut self, snapshot: &Snapshot<W>) {
+        snapshot.apply(self);
+        if self.journal.is_some() {
+            self.journal = Some(Journal::new());
+        }
+        if self.code_writes.is_some() {
+            self.code_writes = Some(SelfModification::new());
+        }
+    }
+
+    /// Runs the program to completion with a comma separated list of inputs and returns
+    /// every output, one per line.
+    pub fn execute(&mut self, input: &str) -> Result<String, IntcodeError> {
+        self.execute_with(input, Program::run)
+    }
+
+    /// `execute` with the function that runs the program up to its next state.
+    fn execute_with<F>(&mut self, input: &str, mut run: F) -> Result<String, IntcodeError>
+    where
+        F: FnMut(&mut Program<W>) -> Result<State<W>, IntcodeError>,
+    {
+        for (index, str) in input
+            .split(',')
+            .filter(|str| !str.trim().is_empty())
+            .enumerate()
+        {
+            self.push_input(parse_word(index, str)?);
+        }
+
+        loop {
+            match run(self)? {
+                State::Output(_) => continue,
+                State::NeedsInput => return Err(IntcodeError::InputRequired { ip: self.index }),
+                State::Halted => break,
+            }
+        }
+
+        Ok(self
+            .output
+            .iter()
+            .map(|value| format!("{}\n", value))
+            .collect())
+    }
+
+    /// Runs until the program produces an output, needs input that is not queued yet,
+    /// or halts. The instruction pointer is left in place on `NeedsInput` and `Halted`
+    /// so calling `run` again resumes where it stopped.
+    pub fn run(&mut self) -> Result<State<W>, IntcodeError> {
+        loop {
+            if let Some(state) = self.step()? {
+                return Ok(state);
+            }
+        }
+    }
+
+    /// Executes a single instruction. Returns the state when the instruction stops `run`
+    /// (an output, missing input or a halt) and `None` when execution can go on. When
+    /// the instruction fails, the instruction pointer stays on it, so it runs again on
+    /// the next call.
+    pub fn step(&mut self) -> Result<Option<State<W>>, IntcodeError> {
+        if self.halted {
+            return Ok(Some(State::Halted));
+        }
+        self.instruction = self.index;
+        let result = self.execute_instruction();
+        if result.is_err() {
+            self.index = self.instruction;
+            if let Some(journal) = self.journal.as_mut() {
+                journal.discard();
+            }
+        }
+        result
+    }
+
+    fn execute_instruction(&mut self) -> Result<Option<State<W>>, IntcodeError> {
+        self.check_limits()?;
+        let modes = self.decode()?;
+        let opcode = modes.opcode;
+        let kind = opcode % 100;
+        let (reads, writes) = match self.instructions.get(kind) {
+            Some(instruction) => (instruction.mnemonic().reads, instruction.mnemonic().writes),
+            None => {
+                return Err(IntcodeError::UnknownOpcode {
+                    ip: self.index,
+                    opcode,
+                })
+            }
+        };
+        if let Some(mode) = modes.unused_mode(reads + writes as usize) {
+            return Err(IntcodeError::InvalidMode {
+                ip: self.index,
+                opcode,
+                mode,
+            });
+        }
+
+        self.trace.begin(self.index, opcode);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.begin(self.index, self.relative_base);
+        }
+        if let Some(code_writes) = self.code_writes.as_mut() {
+            code_writes.execute(self.index, 1 + reads + writes as usize);
+        }
+
+        let mut operands = std::mem::take(&mut self.operands);
+        operands.clear();
+        for arg in 0..reads {
+            self.next();
+            operands.push(modes.get_value(arg, self)?);
+        }
+        let position = if writes {
+            self.next();
+            Some(modes.get_position(reads, self)?)
+        } else {
+            None
+        };
+        self.next();
+
+        let mut context = Context {
+            ip: self.instruction,
+            relative_base: self.relative_base,
+            operands: &operands,
+            input: &mut self.input,
+            consumed: None,
+        };
+        let effect = self.instructions.get(kind).unwrap().execute(&mut context);
+        let consumed = context.consumed;
+        self.operands = operands;
+
+        let effect = match effect {
+            Ok(effect) => effect,
+            Err(error) => {
+                if let Some(value) = consumed {
+                    self.input.push_front(value);
+                }
+                return Err(error);
+            }
+        };
+        if let (Some(journal), Some(value)) = (self.journal.as_mut(), consumed.as_ref()) {
+            journal.input(value);
+        }
+        let state = match effect {
+            Effect::Continue => None,
+            Effect::Write(value) => {
+                let position = position.ok_or(IntcodeError::InvalidEffect {
+                    ip: self.instruction,
+                    opcode,
+                })?;
+                self.write(position, value);
+                None
+            }
+            Effect::Jump(address) => {
+                self.index = address;
+                None
+            }
+            Effect::Output(value) => {
+                if let Some(journal) = self.journal.as_mut() {
+                    journal.output();
+                }
+                self.output.push(value.clone());
+                Some(State::Output(value))
+            }
+            Effect::AdjustRelativeBase(offset) => {
+                self.relative_base =
+                    self.relative_base
+                        .checked_add(offset)
+                        .ok_or(IntcodeError::Overflow {
+                            ip: self.instruction,
+                        })?;
+                None
+            }
+            Effect::NeedsInput => {
+                self.index = self.instruction;
+                return Ok(Some(State::NeedsInput));
+            }
+            Effect::Halt => {
+                self.index = self.instruction;
+                self.halted = true;
+                Some(State::Halted)
+            }
+        };
+
+        self.trace.finish();
+        if let Some(journal) = self.journal.as_mut() {
+            if state == Some(State::Halted) {
+                journal.discard();
+            } else {
+                journal.finish();
+            }
+        }
+        self.count(kind as usize);
+        Ok(state)
+    }
+
+    /// Adds an instruction to this program, replacing any instruction with the same
+    /// opcode, including built-in ones. Fails for opcodes outside 1 to 99 and for
+    /// instructions with more than three operands.
+    pub fn register<I: Instruction<W> + 'static>(
+        &mut self,
+        instruction: I,
+    ) -> Result<(), IntcodeError> {
+        Arc::make_mut(&mut self.instructions).register(Arc::new(instruction))
+    }
+
+    /// Stops any later `step` with `IntcodeError::LimitExceeded` once the program has
+    /// executed `steps` instructions in total.
+    pub fn set_max_steps(&mut self, steps: u64) {
+        self.limits.set_max_steps(steps);
+    }
+
+    /// Stops any later `step` with `IntcodeError::LimitExceeded` once `timeout` has
+    /// passed since the next instruction. The clock is only read every so many
+    /// instructions, so the program may run a little over.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.limits.set_timeout(timeout);
+    }
+
+    pub fn clear_limits(&mut self) {
+        self.limits = Limits::default();
+    }
+
+    /// Number of instructions executed since the program was loaded.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// How often each opcode was executed since the program was loaded, for the
+    /// opcodes that were executed at all.
+    pub fn opcode_counts(&self) -> Vec<(i64, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(opcode, count)| (opcode as i64, *count))
+            .collect()
+    }
+
+    /// Starts recording an undo journal of every executed instruction so execution can
+    /// be reversed with `step_back`. The journal is off by default.
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(Journal::new());
+        }
+    }
+
+    /// Stops recording and forgets the recorded history.
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    /// Number of instructions `step_back` can undo.
+    pub fn journal_len(&self) -> usize {
+        self.journal.as_ref().map_or(0, |journal| journal.len())
+    }
+
+    /// Undoes the last instruction recorded in the journal: restores the memory it wrote,
+    /// the instruction pointer, the relative base, the input it consumed and the output
+    /// it produced. Returns `false` when there is nothing left to undo.
+    pub fn step_back(&mut self) -> bool {
+        let undo = match self.journal.as_mut().and_then(|journal| journal.undo()) {
+            Some(undo) => undo,
+            None => return false,
+        };
+
+        for (address, old) in undo.writes.into_iter().rev() {
+            self.store(address, old);
+        }
+        if let Some(input) = undo.input {
+            self.input.push_front(input);
+        }
+        if undo.output {
+            self.output.pop();
+        }
+        self.index = undo.ip;
+        self.relative_base = undo.relative_base;
+        self.halted = false;
+        true
+    }
+
+    /// Starts keeping track of which addresses are executed and which are written, to
+    /// report writes that land in code with `code_writes`. Off by default.
+    pub fn track_code_writes(&mut self) {
+        if self.code_writes.is_none() {
+            self.code_writes = Some(SelfModification::new());
+        }
+    }
+
+    /// Every write the program made to an address that was executed as part of an
+    /// instruction, before or after the write, since `track_code_writes`. A program
+    /// whose run leaves this empty does not modify its own code.
+    pub fn code_writes(&self) -> &[CodeWrite<W>] {
+        self.code_writes
+            .as_ref()
+            .map_or(&[], |code_writes| code_writes.found())
+    }
+
+    /// Starts counting executions per instruction address, see `profile`. Off by default.
+    pub fn enable_profiling(&mut self) {
+        if self.profile.is_none() {
+            self.profile = Some(Profile::default());
+        }
+    }
+
+    /// What was executed since `enable_profiling`.
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Installs a tracer that receives an event for every executed instruction.
+    /// Tracing is off by default.
+    pub fn set_tracer<T: Tracer<W> + 'static>(&mut self, tracer: T) {
+        self.trace.set(Some(Box::new(tracer)));
+    }
+
+    pub fn remove_tracer(&mut self) {
+        self.trace.set(None);
+    }
+
+    /// Queues a value for the next input instruction.
+    pub fn push_input(&mut self, value: W) {
+        self.input.push_back(value);
+    }
+
+    /// Every value the program has output so far.
+    pub fn output(&self) -> &[W] {
+        &self.output
+    }
+
+    /// Values queued for input instructions that have not been read yet.
+    pub fn input(&self) -> &VecDeque<W> {
+        &self.input
+    }
+
+    /// Address of the next instruction to execute.
+    pub fn ip(&self) -> usize {
+        self.index
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    /// Reads the value stored at `address`; addresses never written to read as 0.
+    pub fn get(&self, address: usize) -> W {
+        self.memory.get(address).clone()
+    }
+
+    /// Overwrites the value at `address`, e.g. to set the noun and verb before running.
+    pub fn set(&mut self, address: usize, value: W) {
+        self.halted = false;
+        self.store(address, value);
+    }
+
+    /// Number of addresses the program was loaded with.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    fn next(&mut self) {
+        self.index += 1;
+    }
+
+    fn check_limits(&mut self) -> Result<(), IntcodeError> {
+        if !self.limits.is_set() {
+            return Ok(());
+        }
+        self.limits
+            .check(self.steps)
+            .map_err(|limit| IntcodeError::LimitExceeded {
+                ip: self.index,
+                limit,
+            })
+    }
+
+    /// Counts the instruction that just ran at `self.instruction`.
+    fn count(&mut self, opcode: usize) {
+        self.steps += 1;
+        self.counts[opcode] += 1;
+        if let Some(profile) = self.profile.as_mut() {
+            profile.record(self.instruction, opcode);
+        }
+    }
+
+    /// Stores the result of an instruction, recording it in the trace.
+    fn write(&mut self, address: usize, value: W) {
+        self.trace.write(address, &value);
+        if let Some(journal) = self.journal.as_mut() {
+            journal.write(address, self.memory.get(address));
+        }
+        if let Some(code_writes) = self.code_writes.as_mut() {
+            let old = self.memory.get(address);
+            code_writes.write(self.instruction, address, old, &value);
+        }
+        self.store(address, value);
+    }
+
+    /// Every change to memory goes through here so self-modifying code never runs a
+    /// stale decoded instruction.
+    fn store(&mut self, address: usize, value: W) {
+        if let Some(decoded) = self.decoded.get_mut(address) {
+            *decoded = None;
+        }
+        self.memory.set(address, value);
+    }
+
+    /// Decodes the instruction at the instruction pointer, from the cache when it has
+    /// been decoded before.
+    fn decode(&mut self) -> Result<ArgumentModes, IntcodeError> {
+        if let Some(Some(modes)) = self.decoded.get(self.index) {
+            return Ok(*modes);
+        }
+
+        let modes = ArgumentModes::new(self.current_opcode()?);
+        if self.index < self.memory.len() {
+            if self.decoded.is_empty() {
+                self.decoded = vec![None; self.memory.len()];
+            }
+            self.decoded[self.index] = Some(modes);
+        }
+        Ok(modes)
+    }
+
+    fn current(&self) -> &W {
+        self.memory.get(self.index)
+    }
+
+    fn current_opcode(&self) -> Result<i64, IntcodeError> {
+        to_number(self.current(), self.index)
+    }
+}
+
+fn parse_word<W: Word>(index: usize, str: &str) -> Result<W, IntcodeError> {
+    str.trim().parse().map_err(|_| IntcodeError::Parse {
+        index,
+        value: String::from(str.trim()),
+    })
+}
+
+fn to_number<W: Word>(value: &W, ip: usize) -> Result<i64, IntcodeError> {
+    value.to_i64().ok_or_else(|| IntcodeError::ValueOutOfRange {
+        ip,
+        value: value.to_string(),
+    })
+}
+
+fn to_address(value: i64, ip: usize) -> Result<usize, IntcodeError> {
+    if value < 0 {
+        return Err(IntcodeError::NegativeAddress { ip, address: value });
+    }
+    Ok(value as usize)
+}
+
+/// Parameter modes of an instruction, decoded from the digits above the two opcode
+/// digits.
+#[derive(Clone, Copy, Debug)]
+struct ArgumentModes {
+    opcode: i64,
+    modes: [u32; 3],
+}
+
+impl ArgumentModes {
+    fn new(opcode: i64) -> ArgumentModes {
+        let digits = opcode.unsigned_abs() / 100;
+        ArgumentModes {
+            opcode,
+            modes: [
+                (digits % 10) as u32,
+                (digits / 10 % 10) as u32,
+                (digits / 100 % 10) as u32,
+            ],
+        }
+    }
+
+    /// Whether the opcode has mode digits beyond the third argument, which no
+    /// instruction has a use for.
+    fn has_extra_modes(&self) -> bool {
+        self.opcode.unsigned_abs() >= 100_000
+    }
+
+    /// The first non-zero mode digit past the `arity` arguments of the instruction,
+    /// including digits beyond the third argument.
+    fn unused_mode(&self, arity: usize) -> Option<u32> {
+        if let Some(mode) = self.modes.iter().skip(arity).find(|mode| **mode != 0) {
+            return Some(*mode);
+        }
+        let mut extra = self.opcode.unsigned_abs() / 100_000;
+        while extra > 0 {
+            let mode = (extra % 10) as u32;
+            if mode != 0 {
+                return Some(mode);
+            }
+            extra /= 10;
+        }
+        None
+    }
+
+    fn get_mode(&self, idx: usize) -> u32 {
+        self.modes.get(idx).copied().unwrap_or(0)
+    }
+
+    /// Reads the argument at the current instruction pointer according to its mode.
+    fn get_value<W: Word>(&self, arg: usize, program: &mut Program<W>) -> Result<W, IntcodeError> {
+        let mode = self.get_mode(arg);
+        let value = match mode {
+            1 => program.current().clone(),
+            _ => program.get(self.resolve(arg, program)?),
+        };
+        program.trace.operand(mode, &value);
+        Ok(value)
+    }
+
+    /// Resolves the address a write argument at the current instruction pointer points to.
+    fn get_position<W: Word>(
+        &self,
+        arg: usize,
+        program: &mut Program<W>,
+    ) -> Result<usize, IntcodeError> {
+        let position = self.resolve(arg, program)?;
+        program.trace.write_mode(self.get_mode(arg));
+        Ok(position)
+    }
+
+    fn resolve<W: Word>(&self, arg: usize, program: &Program<W>) -> Result<usize, IntcodeError> {
+        let ip = program.instruction;
+        let raw = to_number(program.current(), ip)?;
+        match self.get_mode(arg) {
+            0 => to_address(raw, ip),
+            2 => match program.relative_base.checked_add(raw) {
+                Some(address) => to_address(address, ip),
+                None => Err(IntcodeError::Overflow { ip }),
+            },
+            mode => Err(IntcodeError::InvalidMode {
+                ip,
+                opcode: self.opcode,
+                mode,
+            }),
+        }
+    }
+}
+
+impl From<&str> for Program {
+    /// Panics when the program text is malformed; use `Program::new` to handle the error.
+    fn from(str: &str) -> Self {
+        Program::new(str).unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test() {
+        let mut prog = Program::from("1002,4,3,4,33");
+        prog.execute("").unwrap();
+        assert_eq!(99, prog.get(4));
+
+        let mut prog = Program::from("2,4,2,0,4,0,99");
+        assert_eq!("8\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_input() {
+        let mut prog = Program::from("3,0,4,0,99");
+        assert_eq!(String::from("15\n"), prog.execute("15").unwrap());
+    }
+
+    #[test]
+    fn test_program_day2() {
+        let mut prog = Program::from("1,0,0,0,99");
+        prog.execute("").unwrap();
+        assert_eq!(2, prog.get(0));
+
+        let mut prog = Program::from("2,3,0,3,99");
+        prog.execute("").unwrap();
+        assert_eq!(6, prog.get(3));
+
+        let mut prog = Program::from("2,4,4,5,99,0");
+        prog.execute("").unwrap();
+        assert_eq!(9801, prog.get(5));
+
+        let mut prog = Program::from("1,1,1,4,99,5,6,0,99");
+        prog.execute("").unwrap();
+        assert_eq!(30, prog.get(0));
+    }
+
+    #[test]
+    fn test_resume() {
+        let mut prog = Program::from("3,0,4,0,99");
+        assert_eq!(Ok(State::NeedsInput), prog.run());
+        assert_eq!(Ok(State::NeedsInput), prog.run());
+
+        prog.push_input(15);
+        assert_eq!(Ok(State::Output(15)), prog.run());
+        assert_eq!(Ok(State::Halted), prog.run());
+        assert_eq!(Ok(State::Halted), prog.run());
+        assert_eq!(&[15], prog.output());
+    }
+
+    #[test]
+    fn test_resume_multiple_inputs() {
+        let mut prog = Program::from("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+        prog.push_input(3);
+        assert_eq!(Ok(State::NeedsInput), prog.run());
+
+        prog.push_input(4);
+        assert_eq!(Ok(State::Output(7)), prog.run());
+        assert_eq!(Ok(State::Halted), prog.run());
+
+        let mut prog = Program::from("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+        assert_eq!("9\n", prog.execute("4, 5").unwrap());
+    }
+
+    #[test]
+    fn test_relative_base() {
+        let mut prog = Program::from("109,5,204,1,99,0,42");
+        assert_eq!("42\n", prog.execute("").unwrap());
+        assert_eq!(5, prog.relative_base);
+
+        // relative writes for addition, input and compare
+        let mut prog = Program::from("109,10,21101,3,4,-1,204,-1,99,0");
+        assert_eq!("7\n", prog.execute("").unwrap());
+
+        let mut prog = Program::from("109,7,203,0,204,0,99,0");
+        assert_eq!("13\n", prog.execute("13").unwrap());
+
+        let mut prog = Program::from("109,12,21108,8,8,-1,204,-1,109,-1,99,0");
+        assert_eq!("1\n", prog.execute("").unwrap());
+        assert_eq!(11, prog.relative_base);
+    }
+
+    #[test]
+    fn test_memory_beyond_program() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut prog = Program::from(quine);
+        assert_eq!(quine.replace(",", "\n") + "\n", prog.execute("").unwrap());
+        assert_eq!(16, prog.get(100));
+        assert_eq!(0, prog.get(1_000_000));
+        assert_eq!(16, prog.len());
+    }
+
+    #[test]
+    fn test_negative_address() {
+        let mut prog = Program::from("4,-1,99");
+        assert_eq!(
+            Err(IntcodeError::NegativeAddress { ip: 0, address: -1 }),
+            prog.run()
+        );
+
+        let mut prog = Program::from("109,-5,204,0,99");
+        assert_eq!(
+            Err(IntcodeError::NegativeAddress { ip: 2, address: -5 }),
+            prog.run()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            Err(IntcodeError::Parse {
+                index: 2,
+                value: String::from("x")
+            }),
+            Program::new("1,0,x,0,99").map(|_| ())
+        );
+
+        let mut prog = Program::from("1,0,0,0,42");
+        assert_eq!(
+            Err(IntcodeError::UnknownOpcode { ip: 4, opcode: 42 }),
+            prog.run()
+        );
+
+        let mut prog = Program::from("1,0,0,0,11101,1,1,0,99");
+        assert_eq!(
+            Err(IntcodeError::InvalidMode {
+                ip: 4,
+                opcode: 11101,
+                mode: 1
+            }),
+            prog.run()
+        );
+
+        // mode digits the instruction has no argument for
+        let mut prog = Program::from("104,5,1199");
+        assert_eq!(Ok(State::Output(5)), prog.run());
+        assert_eq!(
+            Err(IntcodeError::InvalidMode {
+                ip: 2,
+                opcode: 1199,
+                mode: 1
+            }),
+            prog.run()
+        );
+        let mut prog = Program::from("104,5,2000099");
+        assert_eq!(Ok(State::Output(5)), prog.run());
+        assert_eq!(
+            Err(IntcodeError::InvalidMode {
+                ip: 2,
+                opcode: 2000099,
+                mode: 2
+            }),
+            prog.run()
+        );
+
+        let mut prog = Program::from("301,0,0,0,99");
+        assert_eq!(
+            Err(IntcodeError::InvalidMode {
+                ip: 0,
+                opcode: 301,
+                mode: 3
+            }),
+            prog.run()
+        );
+
+        let mut prog = Program::from("3,0,3,0,99");
+        assert_eq!(
+            Err(IntcodeError::InputRequired { ip: 2 }),
+            prog.execute("5")
+        );
+        assert_eq!(
+            Err(IntcodeError::Parse {
+                index: 0,
+                value: String::from("five")
+            }),
+            Program::from("3,0,99").execute("five")
+        );
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let mut prog = Program::from("1102,34915192,34915192,7,4,7,99,0");
+        assert_eq!("1219070632396864\n", prog.execute("").unwrap());
+
+        let mut prog = Program::from("104,1125899906842624,99");
+        assert_eq!("1125899906842624\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut prog = Program::<i32>::parse("1,0,0,0,1002,9,3,0,99,1000000000").unwrap();
+        assert_eq!(Err(IntcodeError::Overflow { ip: 4 }), prog.run());
+
+        let mut prog = Program::from("1102,10000000000,10000000000,7,4,7,99,0");
+        assert_eq!(Err(IntcodeError::Overflow { ip: 0 }), prog.run());
+    }
+
+    #[test]
+    fn test_retry_after_error() {
+        let mut prog = Program::from("1102,10000000000,10000000000,7,104,5,99,0");
+        assert_eq!(Err(IntcodeError::Overflow { ip: 0 }), prog.run());
+        assert_eq!(0, prog.ip());
+        assert_eq!(Err(IntcodeError::Overflow { ip: 0 }), prog.run());
+
+        prog.set(1, 3);
+        assert_eq!(Ok(State::Output(5)), prog.run());
+        assert_eq!(30_000_000_000, prog.get(7));
+
+        let mut prog = Program::from("3,5,204,-1,99,0");
+        prog.push_input(1);
+        assert_eq!(
+            Err(IntcodeError::NegativeAddress { ip: 2, address: -1 }),
+            prog.run()
+        );
+        assert_eq!(2, prog.ip());
+    }
+
+    #[test]
+    fn test_relative_base_overflow() {
+        let mut prog = Program::from("109,9223372036854775807,109,1,99");
+        assert_eq!(Err(IntcodeError::Overflow { ip: 2 }), prog.run());
+
+        let mut prog = Program::from("109,9223372036854775807,204,1,99");
+        assert_eq!(Err(IntcodeError::Overflow { ip: 2 }), prog.run());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_bigint() {
+        let mut prog =
+            Program::<num_bigint::BigInt>::parse("1102,10000000000,10000000000,7,4,7,99,0")
+                .unwrap();
+        assert_eq!("100000000000000000000\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut prog = Program::from("1101,2,3,7,4,7,99,0");
+        assert_eq!(Ok(None), prog.step());
+        assert_eq!(4, prog.ip());
+        assert_eq!(Ok(Some(State::Output(5))), prog.step());
+        assert_eq!(Ok(Some(State::Halted)), prog.step());
+        assert_eq!(6, prog.ip());
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut prog = Program::from("109,3,3,0,21101,2,3,9,4,0,99");
+        assert!(!prog.step_back());
+
+        prog.enable_journal();
+        prog.push_input(7);
+        assert_eq!("7\n", prog.execute("").unwrap());
+        assert_eq!(4, prog.journal_len());
+
+        // undo the output
+        assert!(prog.step_back());
+        assert_eq!(8, prog.ip());
+        assert!(prog.output().is_empty());
+
+        // undo the relative write of 2 + 3 to address 12
+        assert_eq!(5, prog.get(12));
+        assert!(prog.step_back());
+        assert_eq!(4, prog.ip());
+        assert_eq!(0, prog.get(12));
+
+        // undo the input, which goes back into the queue
+        assert!(prog.step_back());
+        assert_eq!(2, prog.ip());
+        assert_eq!(109, prog.get(0));
+        assert_eq!(
+            &[7],
+            prog.input()
+                .iter()
+                .cloned()
+                .collect::<Vec<i64>>()
+                .as_slice()
+        );
+
+        assert!(prog.step_back());
+        assert_eq!(0, prog.relative_base());
+        assert!(!prog.step_back());
+
+        // replaying gives the same result
+        assert_eq!("7\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_self_modifying_code() {
+        // adds 3 to [18], then turns that addition into a multiplication by 3
+        let mut prog =
+            Program::from("1001,18,3,18,1101,1002,0,0,1007,18,10,19,1005,19,0,4,18,99,2,0");
+        assert_eq!("15\n", prog.execute("").unwrap());
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut prog = Program::from("1101,1,1,5,1105,1,0");
+        prog.set_max_steps(5);
+        let limit = Limit::Steps(5);
+        assert_eq!(
+            Err(IntcodeError::LimitExceeded { ip: 4, limit }),
+            prog.execute("")
+        );
+        assert_eq!(5, prog.steps());
+        assert_eq!(vec![(1, 3), (5, 2)], prog.opcode_counts());
+
+        let mut prog = Program::from("1105,1,0");
+        let timeout = Duration::from_millis(10);
+        prog.set_timeout(timeout);
+        let limit = Limit::Timeout(timeout);
+        assert_eq!(
+            Err(IntcodeError::LimitExceeded { ip: 0, limit }),
+            prog.execute("")
+        );
+
+        prog.clear_limits();
+        prog.set_max_steps(prog.steps() + 2);
+        assert_eq!(Ok(None), prog.step());
+        assert_eq!(Ok(None), prog.step());
+        assert!(prog.step().is_err());
+
+        let mut prog = Program::from("1101,1,1,5,99,0");
+        prog.set_max_steps(2);
+        assert_eq!(Ok(State::Halted), prog.run());
+        assert_eq!(Ok(State::Halted), prog.run());
+        assert_eq!(2, prog.steps());
+        assert_eq!(vec![(1, 1), (99, 1)], prog.opcode_counts());
+    }
+
+    #[test]
+    fn test_set() {
+        let mut prog = Program::from("1,0,0,0,99");
+        prog.set(1, 4);
+        prog.set(2, 4);
+        prog.execute("").unwrap();
+        assert_eq!(198, prog.get(0));
+        assert_eq!(5, prog.len());
+    }
+
+    #[test]
+    fn test_arguments() {
+        assert_eq!([0, 1, 0], ArgumentModes::new(1002).modes);
+        assert_eq!([1, 1, 0], ArgumentModes::new(1102).modes);
+        assert!(!ArgumentModes::new(21101).has_extra_modes());
+        assert!(ArgumentModes::new(121101).has_extra_modes());
+        assert_eq!(None, ArgumentModes::new(21101).unused_mode(3));
+        assert_eq!(Some(2), ArgumentModes::new(20104).unused_mode(1));
+        assert_eq!(Some(1), ArgumentModes::new(1000099).unused_mode(0));
+
+        let arg_modes = ArgumentModes::new(1102);
+        assert_eq!(1, arg_modes.get_mode(0));
+        assert_eq!(1, arg_modes.get_mode(1));
+        assert_eq!(0, arg_modes.get_mode(2));
+
+        let arg_modes = ArgumentModes::new(10102);
+        assert_eq!(1, arg_modes.get_mode(0));
+        assert_eq!(0, arg_modes.get_mode(1));
+        assert_eq!(1, arg_modes.get_mode(2));
+
+        let arg_modes = ArgumentModes::new(21202);
+        assert_eq!(2, arg_modes.get_mode(0));
+        assert_eq!(1, arg_modes.get_mode(1));
+        assert_eq!(2, arg_modes.get_mode(2));
+    }
+
+    #[test]
+    fn test_day5_b_compare() {
+        // positional mode, equals
+        let mut prog = Program::from("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!("1\n", prog.execute("8").unwrap());
+
+        let mut prog = Program::from("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!("0\n", prog.execute("1").unwrap());
+
+        // immediate mode, equals
+        let mut prog = Program::from("3,3,1108,-1,8,3,4,3,99");
+        assert_eq!("1\n", prog.execute("8").unwrap());
+
+        let mut prog = Program::from("3,3,1108,-1,8,3,4,3,99");
+        assert_eq!("0\n", prog.execute("1").unwrap());
+
+        // positional mode, less then
+        let mut prog = Program::from("3,9,7,9,10,9,4,9,99,-1,8");
+        assert_eq!("1\n", prog.execute("7").unwrap());
+
+        let mut prog = Program::from("3,9,7,9,10,9,4,9,99,-1,8");
+        assert_eq!("0\n", prog.execute("9").unwrap());
+
+        // immediate mode, less then
+        let mut prog = Program::from("3,3,1107,-1,8,3,4,3,99");
+        assert_eq!("1\n", prog.execute("7").unwrap());
+
+        let mut prog = Program::from("3,3,1107,-1,8,3,4,3,99");
+        assert_eq!("0\n", prog.execute("9").unwrap());
+    }
+
+    #[test]
+    fn test_day5_b_jump() {
+        let mut prog = Program::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        assert_eq!("0\n", prog.execute("0").unwrap());
+        let mut prog = Program::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        assert_eq!("1\n", prog.execute("5").unwrap());
+    }
+
+    #[test]
+    fn test_day5_b() {
+        let mut prog = Program::from("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+        assert_eq!("999\n", prog.execute("7").unwrap());
+
+        let mut prog = Program::from("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+        assert_eq!("1000\n", prog.execute("8").unwrap());
+
+        let mut prog = Program::from("3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99");
+        assert_eq!("1001\n", prog.execute("9").unwrap());
+    }
+}
@@ -0,0 +1,299 @@
+use super::{IntcodeError, Program, State};
+
+/// A packet sent over a `Network`: the three values a machine outputs in a row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub destination: i64,
+    pub x: i64,
+    pub y: i64,
+}
+
+struct Node {
+    program: Program,
+    /// Values of a packet the machine has only partly output so far.
+    outgoing: Vec<i64>,
+    halted: bool,
+}
+
+/// Machines that run cooperatively, one turn each per round, and send each other
+/// packets. A machine that reads from an empty input queue receives -1.
+pub struct Network {
+    nodes: Vec<Node>,
+    idle: bool,
+}
+
+impl Network {
+    /// Boots `size` copies of `program`, feeding each its address as first input.
+    pub fn new(program: &Program, size: usize) -> Network {
+        let nodes = (0..size)
+            .map(|address| {
+                let mut program = program.clone();
+                program.push_input(address as i64);
+                Node {
+                    program,
+                    outgoing: vec![],
+                    halted: false,
+                }
+            })
+            .collect();
+
+        Network { nodes, idle: false }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Queues `packet` for the machine it is addressed to. Returns false when no
+    /// machine has that address.
+    pub fn send(&mut self, packet: &Packet) -> bool {
+        let node = match self.node(packet.destination) {
+            Some(node) => node,
+            None => return false,
+        };
+        node.program.push_input(packet.x);
+        node.program.push_input(packet.y);
+        true
+    }
+
+    /// Runs every machine that has not halted until it waits for input, delivering
+    /// packets as they are sent, and returns the packets addressed outside the network.
+    pub fn round(&mut self) -> Result<Vec<Packet>, IntcodeError> {
+        if self.nodes.iter().all(|node| node.halted) {
+            return Err(IntcodeError::UnexpectedHalt { ip: self.ip() });
+        }
+
+        let mut external = vec![];
+        let mut idle = true;
+        for idx in 0..self.nodes.len() {
+            let mut sent = vec![];
+            let node = &mut self.nodes[idx];
+            if node.halted {
+                continue;
+            }
+            if node.program.input().is_empty() {
+                node.program.push_input(-1);
+            } else {
+                idle = false;
+            }
+
+            loop {
+                match node.program.run()? {
+                    State::Output(value) => {
+                        idle = false;
+                        node.outgoing.push(value);
+                        if let [destination, x, y] = node.outgoing[..] {
+                            sent.push(Packet { destination, x, y });
+                            node.outgoing.clear();
+                        }
+                    }
+                    State::NeedsInput => break,
+                    State::Halted => {
+                        node.halted = true;
+                        break;
+                    }
+                }
+            }
+
+            for packet in sent {
+                if !self.send(&packet) {
+                    external.push(packet);
+                }
+            }
+        }
+        self.idle = idle;
+        Ok(external)
+    }
+
+    /// Whether nothing happened in the last round: every machine found its input
+    /// queue empty and none of them output anything.
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Runs rounds until a machine sends a packet to `address`, outside the network.
+    /// Fails with `NetworkIdle` if the network goes idle first.
+    pub fn listen(&mut self, address: i64) -> Result<Packet, IntcodeError> {
+        loop {
+            let packets = self.round()?;
+            if let Some(packet) = packets.into_iter().find(|p| p.destination == address) {
+                return Ok(packet);
+            }
+            if self.is_idle() {
+                return Err(IntcodeError::NetworkIdle);
+            }
+        }
+    }
+
+    fn node(&mut self, address: i64) -> Option<&mut Node> {
+        if address < 0 {
+            return None;
+        }
+        self.nodes.get_mut(address as usize)
+    }
+
+    /// Where machine 0 stopped, for errors about the network as a whole.
+    fn ip(&self) -> usize {
+        self.nodes.first().map_or(0, |node| node.program.ip())
+    }
+}
+
+/// A monitor node outside the network that keeps the last packet sent to `address`
+/// and sends it on to machine 0 whenever the network goes idle.
+pub struct Nat {
+    address: i64,
+    last: Option<Packet>,
+    delivered: Vec<Packet>,
+}
+
+impl Nat {
+    pub fn new(address: i64) -> Nat {
+        Nat {
+            address,
+            last: None,
+            delivered: vec![],
+        }
+    }
+
+    /// The packets sent to machine 0 so far, oldest first.
+    pub fn delivered(&self) -> &[Packet] {
+        &self.delivered
+    }
+
+    /// Runs `network` until this node sends machine 0 the same `y` twice in a row and
+    /// returns that packet. Fails with `NetworkIdle` if the network goes idle before
+    /// anything was sent to this node.
+    pub fn run(&mut self, network: &mut Network) -> Result<Packet, IntcodeError> {
+        loop {
+            for packet in network.round()? {
+                if packet.destination == self.address {
+                    self.last = Some(packet);
+                }
+            }
+            if !network.is_idle() {
+                continue;
+            }
+
+            let packet = match &self.last {
+                Some(last) => Packet {
+                    destination: 0,
+                    x: last.x,
+                    y: last.y,
+                },
+                None => return Err(IntcodeError::NetworkIdle),
+            };
+            network.send(&packet);
+            let repeated = self.delivered.last().is_some_and(|last| last.y == packet.y);
+            self.delivered.push(packet.clone());
+            if repeated {
+                return Ok(packet);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+
+    // machine 0 starts a packet that every machine passes on to the next with `y`
+    // counting the hops, the last one to 255; when machine 0 receives a packet
+    // it starts over
+    const RELAY: &str = "
+                IN -> [address]
+                JNZ [address], #receive
+                OUT #1
+                OUT #0
+                OUT #0
+        receive: IN -> [x]
+                EQ [x], #-1 -> [test]
+                JNZ [test], #receive
+                IN -> [y]
+                JNZ [address], #forward
+                ADD #0, #-1 -> [y]
+        forward: ADD [address], #1 -> [destination]
+                EQ [destination], #4 -> [test]
+                JZ [test], #send
+                ADD #0, #255 -> [destination]
+        send:   ADD [y], #1 -> [y]
+                OUT [destination]
+                OUT [x]
+                OUT [y]
+                JNZ #1, #receive
+        address: DATA 0
+        x:      DATA 0
+        y:      DATA 0
+        destination: DATA 0
+        test:   DATA 0
+    ";
+
+    fn relay() -> Program {
+        Program::from(assemble(RELAY).unwrap().as_str())
+    }
+
+    #[test]
+    fn test_listen() {
+        let mut network = Network::new(&relay(), 4);
+        assert_eq!(4, network.len());
+        assert_eq!(
+            Ok(Packet {
+                destination: 255,
+                x: 0,
+                y: 3
+            }),
+            network.listen(255)
+        );
+
+        network.round().unwrap();
+        assert!(network.is_idle());
+        assert_eq!(Err(IntcodeError::NetworkIdle), network.listen(255));
+        assert!(network.send(&Packet {
+            destination: 2,
+            x: 7,
+            y: 10
+        }));
+        assert!(!network.send(&Packet {
+            destination: 4,
+            x: 7,
+            y: 10
+        }));
+        assert_eq!(
+            Ok(Packet {
+                destination: 255,
+                x: 7,
+                y: 12
+            }),
+            network.listen(255)
+        );
+    }
+
+    #[test]
+    fn test_nat() {
+        let mut network = Network::new(&relay(), 4);
+        let mut nat = Nat::new(255);
+        let packet = Packet {
+            destination: 0,
+            x: 0,
+            y: 3,
+        };
+        assert_eq!(Ok(packet.clone()), nat.run(&mut network));
+        assert_eq!(&[packet.clone(), packet], nat.delivered());
+
+        let mut network = Network::new(&Program::from("3,5,1105,1,0,0"), 2);
+        assert_eq!(
+            Err(IntcodeError::NetworkIdle),
+            Nat::new(255).run(&mut network)
+        );
+
+        let mut network = Network::new(&Program::from("3,0,99"), 2);
+        assert_eq!(
+            Err(IntcodeError::UnexpectedHalt { ip: 2 }),
+            Nat::new(255).run(&mut network)
+        );
+    }
+}
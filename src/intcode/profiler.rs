@@ -0,0 +1,235 @@
+use super::disassembler::describe;
+use super::Mnemonic;
+use super::Program;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Execution counts per instruction address, collected while profiling is enabled
+/// with `Program::enable_profiling`.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    /// How often the instruction at an address was executed, and its opcode, without
+    /// parameter modes, the last time. Keyed by address, as code may run anywhere in
+    /// memory.
+    counts: BTreeMap<usize, (u64, u8)>,
+    /// Addresses that start a basic block: the first instruction and every
+    /// instruction executed right after a jump.
+    leaders: BTreeSet<usize>,
+    after_jump: bool,
+}
+
+/// A run of instructions that is only entered at `start` and only left after its
+/// last instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    /// Address just past the last instruction.
+    pub end: usize,
+    pub instructions: usize,
+    /// How often the block was entered.
+    pub count: u64,
+    /// Instructions executed in the block in total.
+    pub executed: u64,
+}
+
+impl Profile {
+    pub(crate) fn record(&mut self, ip: usize, opcode: usize) {
+        if self.after_jump || self.leaders.is_empty() {
+            self.leaders.insert(ip);
+        }
+        let entry = self.counts.entry(ip).or_insert((0, 0));
+        entry.0 += 1;
+        entry.1 = opcode as u8;
+        self.after_jump = opcode == 5 || opcode == 6;
+    }
+
+    /// How often the instruction at `address` was executed.
+    pub fn count(&self, address: usize) -> u64 {
+        self.counts.get(&address).map_or(0, |(count, _)| *count)
+    }
+
+    /// Executed instruction addresses with their counts, most executed first.
+    pub fn hot_spots(&self) -> Vec<(usize, u64)> {
+        let mut hot_spots: Vec<(usize, u64)> = self
+            .counts
+            .iter()
+            .map(|(address, (count, _))| (*address, *count))
+            .collect();
+        hot_spots.sort_by_key(|(address, count)| (std::cmp::Reverse(*count), *address));
+        hot_spots
+    }
+
+    /// The basic blocks that were executed, in address order.
+    pub fn blocks(&self) -> Vec<Block> {
+        self.leaders
+            .iter()
+            .map(|start| {
+                let mut block = Block {
+                    start: *start,
+                    end: *start,
+                    instructions: 0,
+                    count: self.count(*start),
+                    executed: 0,
+                };
+                while let Some((count, kind)) = self.counts.get(&block.end).copied() {
+                    block.instructions += 1;
+                    block.executed += count;
+                    block.end += Mnemonic::find(kind as i64).map_or(1, |m| 1 + m.arity());
+                    if kind == 5 || kind == 6 || kind == 99 || self.leaders.contains(&block.end) {
+                        break;
+                    }
+                }
+                block
+            })
+            .collect()
+    }
+
+    /// A text report of the `top` most executed instructions and basic blocks, with
+    /// instructions disassembled from the current memory of `program`.
+    pub fn report(&self, program: &Program, top: usize) -> String {
+        let total: u64 = self.counts.values().map(|(count, _)| count).sum();
+        let mut report = String::new();
+        writeln!(report, "{} instructions executed", total).unwrap();
+
+        writeln!(report, "\nhot instructions:").unwrap();
+        for (address, count) in self.hot_spots().into_iter().take(top) {
+            let (text, _) = describe(program, address);
+            writeln!(
+                report,
+                "{:>10} {:>5.1}% {:>5}: {}",
+                count,
+                percentage(count, total),
+                address,
+                text
+            )
+            .unwrap();
+        }
+
+        writeln!(report, "\nhot blocks:").unwrap();
+        let mut blocks = self.blocks();
+        blocks.sort_by_key(|block| (std::cmp::Reverse(block.executed), block.start));
+        for block in blocks.into_iter().take(top) {
+            writeln!(
+                report,
+                "{:>10} {:>5.1}% {:>5}..{:<5} entered {} times, {} instructions",
+                block.executed,
+                percentage(block.executed, total),
+                block.start,
+                block.end,
+                block.count,
+                block.instructions
+            )
+            .unwrap();
+        }
+        report
+    }
+
+    /// The profile as folded stacks for flamegraph tools, one `intcode;<block> <count>`
+    /// line per basic block weighted by the instructions executed in it.
+    pub fn folded(&self) -> String {
+        self.blocks()
+            .iter()
+            .map(|block| {
+                format!(
+                    "intcode;{}..{} {}\n",
+                    block.start, block.end, block.executed
+                )
+            })
+            .collect()
+    }
+}
+
+fn percentage(count: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    100.0 * count as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // doubles its input until it is at least 100
+    const DOUBLER: &str = "3,20,1002,20,2,20,1007,20,100,21,1005,21,2,4,20,99";
+
+    #[test]
+    fn test_counts_and_blocks() {
+        let mut prog = Program::from(DOUBLER);
+        prog.enable_profiling();
+        assert_eq!("120\n", prog.execute("15").unwrap());
+
+        let profile = prog.profile().unwrap();
+        assert_eq!(1, profile.count(0));
+        assert_eq!(3, profile.count(2));
+        assert_eq!(0, profile.count(1));
+        assert_eq!((2, 3), profile.hot_spots()[0]);
+        assert_eq!(
+            vec![
+                Block {
+                    start: 0,
+                    end: 2,
+                    instructions: 1,
+                    count: 1,
+                    executed: 1
+                },
+                Block {
+                    start: 2,
+                    end: 13,
+                    instructions: 3,
+                    count: 3,
+                    executed: 9
+                },
+                Block {
+                    start: 13,
+                    end: 16,
+                    instructions: 2,
+                    count: 1,
+                    executed: 2
+                },
+            ],
+            profile.blocks()
+        );
+        assert_eq!(
+            "intcode;0..2 1\nintcode;2..13 9\nintcode;13..16 2\n",
+            profile.folded()
+        );
+    }
+
+    #[test]
+    fn test_far_code() {
+        // jumps to an address far beyond the image and halts there
+        let mut prog = Program::from("1101,99,0,100000000000,1105,1,100000000000");
+        prog.enable_profiling();
+        prog.execute("").unwrap();
+
+        let profile = prog.profile().unwrap();
+        assert_eq!(1, profile.count(100000000000));
+        assert_eq!(
+            "intcode;0..7 2\nintcode;100000000000..100000000001 1\n",
+            profile.folded()
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let mut prog = Program::from(DOUBLER);
+        prog.enable_profiling();
+        prog.execute("15").unwrap();
+
+        let report = prog.profile().unwrap().report(&prog, 2);
+        assert_eq!(
+            "12 instructions executed
+
+hot instructions:
+         3  25.0%     2: MUL [20], #2 -> [20]
+         3  25.0%     6: LT [20], #100 -> [21]
+
+hot blocks:
+         9  75.0%     2..13    entered 3 times, 3 instructions
+         2  16.7%    13..16    entered 1 times, 2 instructions
+",
+            report
+        );
+    }
+}
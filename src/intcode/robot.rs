@@ -0,0 +1,191 @@
+use super::{IntcodeError, Program, State};
+use crate::grid::{bounds, Directions, Pos};
+use std::collections::HashMap;
+
+pub const BLACK: i64 = 0;
+pub const WHITE: i64 = 1;
+
+/// The panels of a hull, all black until they are painted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Hull {
+    panels: HashMap<Pos, i64>,
+}
+
+impl Hull {
+    pub fn new() -> Hull {
+        Hull::default()
+    }
+
+    pub fn colour(&self, pos: &Pos) -> i64 {
+        self.panels.get(pos).copied().unwrap_or(BLACK)
+    }
+
+    pub fn paint(&mut self, pos: Pos, colour: i64) {
+        self.panels.insert(pos, colour);
+    }
+
+    /// Number of panels painted at least once, whatever their colour is now.
+    pub fn painted(&self) -> usize {
+        self.panels.len()
+    }
+
+    /// Draws the painted part of the hull, top row first, with `#` for white panels
+    /// and `.` for everything else.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        let (min, max) = match bounds(self.panels.keys()) {
+            Some(bounds) => bounds,
+            None => return text,
+        };
+        for y in (min.y..=max.y).rev() {
+            for x in min.x..=max.x {
+                text.push(match self.colour(&Pos { x, y }) {
+                    WHITE => '#',
+                    _ => '.',
+                });
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Drives a program as a robot that moves over a hull: it reads the colour of the
+/// panel it is on and answers with the colour to paint it and the way to turn, 0
+/// for left and 1 for right, before moving forward one panel.
+pub struct Robot {
+    program: Program,
+    position: Pos,
+    heading: Directions,
+    hull: Hull,
+}
+
+impl Robot {
+    /// Puts the robot on the start of a black hull, facing up.
+    pub fn new(program: Program) -> Robot {
+        Robot {
+            program,
+            position: Pos::start(),
+            heading: Directions::U,
+            hull: Hull::new(),
+        }
+    }
+
+    pub fn position(&self) -> &Pos {
+        &self.position
+    }
+
+    pub fn heading(&self) -> Directions {
+        self.heading
+    }
+
+    pub fn hull(&self) -> &Hull {
+        &self.hull
+    }
+
+    /// The hull, e.g. to paint panels before the robot starts.
+    pub fn hull_mut(&mut self) -> &mut Hull {
+        &mut self.hull
+    }
+
+    /// Runs the program until it halts. Fails with `UnexpectedHalt` when it halts
+    /// between a colour and a turn, and with `UnexpectedOutput` for a turn that is
+    /// neither 0 nor 1.
+    pub fn run(&mut self) -> Result<(), IntcodeError> {
+        let mut colour = None;
+        loop {
+            match self.program.run()? {
+                State::NeedsInput => {
+                    let under = self.hull.colour(&self.position);
+                    self.program.push_input(under);
+                }
+                State::Output(value) => match colour.take() {
+                    None => colour = Some(value),
+                    Some(colour) => {
+                        self.hull.paint(self.position.clone(), colour);
+                        self.heading = match value {
+                            0 => self.heading.turn_left(),
+                            1 => self.heading.turn_right(),
+                            _ => {
+                                return Err(IntcodeError::UnexpectedOutput {
+                                    ip: self.program.instruction,
+                                    value,
+                                })
+                            }
+                        };
+                        self.position = self.position.step(self.heading);
+                    }
+                },
+                State::Halted if colour.is_some() => {
+                    return Err(IntcodeError::UnexpectedHalt {
+                        ip: self.program.ip(),
+                    })
+                }
+                State::Halted => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::assembler::assemble;
+
+    /// A program that reads a colour before every pair of `outputs` and ignores it.
+    fn scripted(outputs: &[(i64, i64)]) -> Program {
+        let mut source: String = outputs
+            .iter()
+            .map(|(colour, turn)| format!("IN -> [seen]\nOUT #{}\nOUT #{}\n", colour, turn))
+            .collect();
+        source.push_str("HALT\nseen: DATA 0");
+        Program::from(assemble(&source).unwrap().as_str())
+    }
+
+    #[test]
+    fn test_painting() {
+        let program = scripted(&[(1, 0), (0, 0), (1, 0), (1, 0), (0, 1), (1, 0), (1, 0)]);
+        let mut robot = Robot::new(program);
+        robot.run().unwrap();
+
+        assert_eq!(6, robot.hull().painted());
+        assert_eq!(&Pos { x: 0, y: 1 }, robot.position());
+        assert_eq!(Directions::L, robot.heading());
+        assert_eq!("..#\n..#\n##.\n", robot.hull().render());
+    }
+
+    #[test]
+    fn test_reads_colour() {
+        // paints every panel the opposite colour of what it sees while turning right,
+        // so it goes round a square twice
+        let source = "
+            loop:   IN -> [seen]
+                    EQ [seen], #0 -> [seen]
+                    OUT [seen]
+                    OUT #1
+                    ADD [count], #-1 -> [count]
+                    JNZ [count], #loop
+                    HALT
+            seen:   DATA 0
+            count:  DATA 8
+        ";
+        let mut robot = Robot::new(Program::from(assemble(source).unwrap().as_str()));
+        robot.hull_mut().paint(Pos { x: 1, y: 0 }, WHITE);
+        robot.run().unwrap();
+
+        assert_eq!(4, robot.hull().painted());
+        assert_eq!(".#\n..\n", robot.hull().render());
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut robot = Robot::new(Program::from("3,0,104,1,99"));
+        assert_eq!(Err(IntcodeError::UnexpectedHalt { ip: 4 }), robot.run());
+
+        let mut robot = Robot::new(Program::from("104,1,104,2,99"));
+        assert_eq!(
+            Err(IntcodeError::UnexpectedOutput { ip: 2, value: 2 }),
+            robot.run()
+        );
+    }
+}
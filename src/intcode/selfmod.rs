@@ -0,0 +1,117 @@
+use super::Word;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A write by the program to an address that is also executed as part of an
+/// instruction, either before or after the write.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodeWrite<W = i64> {
+    pub address: usize,
+    /// Address of the instruction that wrote.
+    pub ip: usize,
+    pub old: W,
+    pub new: W,
+}
+
+/// `[3]: 0 -> 2 by ip 0`
+impl<W: Word> fmt::Display for CodeWrite<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}]: {} -> {} by ip {}",
+            self.address, self.old, self.new, self.ip
+        )
+    }
+}
+
+/// Which addresses were executed and which were written, for `Program::code_writes`.
+#[derive(Clone, Debug)]
+pub(crate) struct SelfModification<W> {
+    executed: HashSet<usize>,
+    /// The last write to each address that has not been executed yet.
+    pending: HashMap<usize, CodeWrite<W>>,
+    found: Vec<CodeWrite<W>>,
+}
+
+impl<W: Word> SelfModification<W> {
+    pub fn new() -> SelfModification<W> {
+        SelfModification {
+            executed: HashSet::new(),
+            pending: HashMap::new(),
+            found: vec![],
+        }
+    }
+
+    pub fn found(&self) -> &[CodeWrite<W>] {
+        &self.found
+    }
+
+    /// Marks the `len` cells of the instruction at `ip` as code.
+    pub fn execute(&mut self, ip: usize, len: usize) {
+        for address in ip..ip + len {
+            if self.executed.insert(address) {
+                if let Some(write) = self.pending.remove(&address) {
+                    self.found.push(write);
+                }
+            }
+        }
+    }
+
+    pub fn write(&mut self, ip: usize, address: usize, old: &W, new: &W) {
+        let write = CodeWrite {
+            address,
+            ip,
+            old: old.clone(),
+            new: new.clone(),
+        };
+        if self.executed.contains(&address) {
+            self.found.push(write);
+        } else {
+            self.pending.insert(address, write);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Program, State};
+    use super::*;
+
+    #[test]
+    fn test_code_writes() {
+        // overwrites its own write address, then the halt at 8 with an output
+        let mut prog = Program::from("1,1,2,3,1101,100,4,8,99,0");
+        prog.track_code_writes();
+        assert_eq!(Ok(State::Output(0)), prog.run());
+        assert_eq!(
+            vec![
+                CodeWrite {
+                    address: 3,
+                    ip: 0,
+                    old: 3,
+                    new: 3
+                },
+                CodeWrite {
+                    address: 8,
+                    ip: 4,
+                    old: 99,
+                    new: 104
+                },
+            ],
+            prog.code_writes()
+        );
+        assert_eq!("[8]: 99 -> 104 by ip 4", prog.code_writes()[1].to_string());
+    }
+
+    #[test]
+    fn test_data_writes() {
+        let mut prog = Program::from("3,9,1002,9,2,9,4,9,99,0");
+        prog.track_code_writes();
+        assert_eq!("10\n", prog.execute("5").unwrap());
+        assert!(prog.code_writes().is_empty());
+
+        let mut prog = Program::from("3,9,1002,9,2,9,4,9,99,0");
+        prog.execute("5").unwrap();
+        assert!(prog.code_writes().is_empty());
+    }
+}
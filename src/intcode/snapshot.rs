@@ -0,0 +1,269 @@
+use super::memory::Memory;
+use super::{InstructionSet, IntcodeError, Program, Word};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Saved machine state: memory, instruction pointer, relative base, whether the
+/// program halted and the input and output queues. Tracers and the undo journal are
+/// not part of it.
+///
+/// Memory is shared copy-on-write with the program it was taken from, so taking and
+/// restoring snapshots is cheap. A snapshot can be written to a file with `save` and
+/// read back with `load`. Registered instructions carry over to programs started with
+/// `restore`, but are not saved to a file.
+#[derive(Clone, Debug)]
+pub struct Snapshot<W: Word = i64> {
+    memory: Memory<W>,
+    ip: usize,
+    relative_base: i64,
+    halted: bool,
+    input: VecDeque<W>,
+    output: Vec<W>,
+    instructions: Arc<InstructionSet<W>>,
+}
+
+impl<W: Word> Snapshot<W> {
+    pub(crate) fn new(program: &Program<W>) -> Snapshot<W> {
+        Snapshot {
+            memory: program.memory.clone(),
+            ip: program.index,
+            relative_base: program.relative_base,
+            halted: program.halted,
+            input: program.input.clone(),
+            output: program.output.clone(),
+            instructions: program.instructions.clone(),
+        }
+    }
+
+    /// Starts a new program from this state, with the instructions of the program the
+    /// snapshot was taken from.
+    pub fn restore(&self) -> Program<W> {
+        let mut program = Program::with_memory(self.memory.clone());
+        program.instructions = self.instructions.clone();
+        program.restore(self);
+        program
+    }
+
+    pub(crate) fn apply(&self, program: &mut Program<W>) {
+        program.memory = self.memory.clone();
+        program.decoded.clear();
+        program.index = self.ip;
+        program.instruction = self.ip;
+        program.relative_base = self.relative_base;
+        program.halted = self.halted;
+        program.input = self.input.clone();
+        program.output = self.output.clone();
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Snapshot<W>> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// One `key value` line per field, with lists comma separated. Pages of memory past
+/// the program image each get a `page <address> <values>` line.
+impl<W: Word> fmt::Display for Snapshot<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "ip {}", self.ip)?;
+        writeln!(f, "relative_base {}", self.relative_base)?;
+        writeln!(f, "halted {}", self.halted)?;
+        writeln!(f, "input {}", join(self.input.iter()))?;
+        writeln!(f, "output {}", join(self.output.iter()))?;
+        writeln!(f, "image {}", join(self.memory.image().iter()))?;
+        for (address, values) in self.memory.pages() {
+            writeln!(f, "page {} {}", address, join(values.iter()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Word> FromStr for Snapshot<W> {
+    type Err = IntcodeError;
+
+    fn from_str(str: &str) -> Result<Snapshot<W>, IntcodeError> {
+        let mut ip = None;
+        let mut relative_base = None;
+        let mut halted = false;
+        let mut input = None;
+        let mut output = None;
+        let mut memory = None;
+        let mut pages = vec![];
+
+        for (index, line) in str.lines().enumerate() {
+            let invalid = IntcodeError::InvalidSnapshot { line: index + 1 };
+            let mut fields = line.splitn(2, ' ');
+            let key = fields.next().unwrap_or("");
+            let value = fields.next().unwrap_or("").trim();
+            match key {
+                "" => {}
+                "ip" => ip = Some(value.parse().map_err(|_| invalid)?),
+                "relative_base" => relative_base = Some(value.parse().map_err(|_| invalid)?),
+                "halted" => halted = value.parse().map_err(|_| invalid)?,
+                "input" => input = Some(split(value).ok_or(invalid)?),
+                "output" => output = Some(split(value).ok_or(invalid)?),
+                "image" => memory = Some(Memory::new(split(value).ok_or(invalid)?)),
+                "page" => {
+                    let mut fields = value.splitn(2, ' ');
+                    let address = fields.next().and_then(|address| address.parse().ok());
+                    let values = split(fields.next().unwrap_or(""));
+                    match (address, values) {
+                        (Some(address), Some(values)) => pages.push((address, values)),
+                        _ => return Err(invalid),
+                    }
+                }
+                _ => return Err(invalid),
+            }
+        }
+
+        let missing = IntcodeError::InvalidSnapshot {
+            line: str.lines().count(),
+        };
+        let mut memory: Memory<W> = memory.ok_or_else(|| missing.clone())?;
+        for (address, values) in pages {
+            memory.load(address, values);
+        }
+
+        Ok(Snapshot {
+            memory,
+            ip: ip.ok_or_else(|| missing.clone())?,
+            relative_base: relative_base.ok_or_else(|| missing.clone())?,
+            halted,
+            input: input.ok_or_else(|| missing.clone())?.into(),
+            output: output.ok_or(missing)?,
+            instructions: Arc::new(InstructionSet::default()),
+        })
+    }
+}
+
+fn join<'a, W: Word + 'a, I: Iterator<Item = &'a W>>(values: I) -> String {
+    values
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn split<W: Word>(str: &str) -> Option<Vec<W>> {
+    str.split(',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Context, Effect, Instruction, Mnemonic, State};
+    use super::*;
+
+    /// Outputs twice its operand instead of the operand itself.
+    struct Double;
+
+    impl Instruction for Double {
+        fn mnemonic(&self) -> &Mnemonic {
+            &Mnemonic {
+                opcode: 4,
+                name: "OUT",
+                reads: 1,
+                writes: false,
+            }
+        }
+
+        fn execute(&self, context: &mut Context<i64>) -> Result<Effect<i64>, IntcodeError> {
+            Ok(Effect::Output(context.operand(0) * 2))
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut prog = Program::from("3,9,4,9,3,10,4,10,99,0,0");
+        prog.push_input(5);
+        prog.run().unwrap();
+        let snapshot = prog.snapshot();
+
+        assert_eq!("5\n7\n", prog.execute("7").unwrap());
+        assert_eq!(7, prog.get(10));
+
+        prog.restore(&snapshot);
+        assert_eq!(0, prog.get(10));
+        assert_eq!("5\n8\n", prog.execute("8").unwrap());
+
+        let mut copy = snapshot.restore();
+        assert_eq!(4, copy.ip());
+        assert_eq!("5\n9\n", copy.execute("9").unwrap());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut prog = Program::from("109,2000,21101,2,3,1,3,0,99");
+        prog.push_input(4);
+        prog.step().unwrap();
+        prog.step().unwrap();
+        prog.push_input(5);
+
+        let text = prog.snapshot().to_string();
+        assert!(text.starts_with(
+            "ip 6\nrelative_base 2000\nhalted false\ninput 4,5\noutput \nimage 109,2000,21101,2,3,1,3,0,99\npage 9 "
+        ));
+
+        let snapshot: Snapshot = text.parse().unwrap();
+        assert_eq!(text, snapshot.to_string());
+        let mut copy = snapshot.restore();
+        assert_eq!(5, copy.get(2001));
+        copy.run().unwrap();
+        assert_eq!(4, copy.get(0));
+
+        assert_eq!(
+            Err(IntcodeError::InvalidSnapshot { line: 2 }),
+            "ip 0\nrelative_base x\n".parse::<Snapshot>().map(|_| ())
+        );
+        assert_eq!(
+            Err(IntcodeError::InvalidSnapshot { line: 1 }),
+            "ip 0\n".parse::<Snapshot>().map(|_| ())
+        );
+        assert_eq!(
+            Err(IntcodeError::InvalidSnapshot { line: 2 }),
+            "ip 0\nhalted maybe\n".parse::<Snapshot>().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_instructions_and_halt() {
+        let mut prog = Program::from("104,21,99");
+        prog.register(Double).unwrap();
+        let snapshot = prog.snapshot();
+        assert_eq!("42\n", snapshot.restore().execute("").unwrap());
+
+        assert_eq!("42\n", prog.execute("").unwrap());
+        let snapshot = prog.snapshot();
+        assert!(snapshot.to_string().contains("\nhalted true\n"));
+        let mut copy = snapshot.restore();
+        assert_eq!(Ok(State::Halted), copy.run());
+        assert_eq!(0, copy.steps());
+
+        let mut copy = snapshot.to_string().parse::<Snapshot>().unwrap().restore();
+        assert_eq!(Ok(State::Halted), copy.run());
+        assert_eq!(0, copy.steps());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("intcode-snapshot-{}", std::process::id()));
+        let prog = Program::from("1,0,0,0,99");
+        prog.snapshot().save(&path).unwrap();
+
+        let mut copy = Snapshot::load(&path).unwrap().restore();
+        fs::remove_file(&path).unwrap();
+        copy.execute("").unwrap();
+        assert_eq!(2, copy.get(0));
+    }
+}
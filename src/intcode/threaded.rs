@@ -0,0 +1,123 @@
+use super::{IntcodeError, Program, State, Word};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+/// Runs `program` on a thread of its own. Input instructions block until a value
+/// arrives on `input` and every output is sent on `output`; outputs sent after the
+/// receiving end hung up are dropped.
+///
+/// The thread returns the program once it halts, or fails with `InputRequired` when
+/// it needs input and every sender of `input` is gone.
+pub fn spawn<W: Word + Send + Sync + 'static>(
+    mut program: Program<W>,
+    input: Receiver<W>,
+    output: Sender<W>,
+) -> JoinHandle<Result<Program<W>, IntcodeError>> {
+    thread::spawn(move || loop {
+        match program.run()? {
+            State::Output(value) => {
+                let _ = output.send(value);
+            }
+            State::NeedsInput => match input.recv() {
+                Ok(value) => program.push_input(value),
+                Err(_) => return Err(IntcodeError::InputRequired { ip: program.ip() }),
+            },
+            State::Halted => return Ok(program),
+        }
+    })
+}
+
+/// A program running on its own thread, with channels for its input and output.
+pub struct Machine<W: Word = i64> {
+    pub input: Sender<W>,
+    pub output: Receiver<W>,
+    handle: JoinHandle<Result<Program<W>, IntcodeError>>,
+}
+
+impl<W: Word + Send + Sync + 'static> Machine<W> {
+    pub fn start(program: Program<W>) -> Machine<W> {
+        let (input, receiver) = channel();
+        let (sender, output) = channel();
+        Machine {
+            input,
+            output,
+            handle: spawn(program, receiver, sender),
+        }
+    }
+
+    /// Closes the input channel and waits for the program to halt.
+    pub fn join(self) -> Result<Program<W>, IntcodeError> {
+        drop(self.input);
+        self.handle.join().expect("Intcode thread panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_machine() {
+        let machine = Machine::start(Program::from("3,9,8,9,10,9,4,9,99,-1,8"));
+        machine.input.send(8).unwrap();
+        assert_eq!(Ok(1), machine.output.recv());
+        let program = machine.join().unwrap();
+        assert_eq!(&[1], program.output());
+
+        let machine = Machine::start(Program::from("3,0,99"));
+        assert_eq!(
+            Err(IntcodeError::InputRequired { ip: 0 }),
+            machine.join().map(|_| ())
+        );
+    }
+
+    #[test]
+    fn test_feedback_loop() {
+        let program = Program::from(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        );
+        let phases = [9, 8, 7, 6, 5];
+        let (first, mut input) = channel();
+        let mut handles = vec![];
+        for (idx, phase) in phases.iter().enumerate() {
+            let (sender, receiver) = channel();
+            if idx == 0 {
+                first.send(*phase).unwrap();
+                first.send(0).unwrap();
+            }
+            if idx + 1 < phases.len() {
+                sender.send(phases[idx + 1]).unwrap();
+            }
+            handles.push(spawn(program.clone(), input, sender));
+            input = receiver;
+        }
+
+        // passes the last machine's signals back to the first, keeping the last one
+        let mut thrust = None;
+        for signal in input.iter() {
+            thrust = Some(signal);
+            let _ = first.send(signal);
+        }
+        assert_eq!(Some(139629729), thrust);
+        for handle in handles {
+            assert!(handle.join().unwrap().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_many_threads() {
+        let program = Program::from("3,20,1002,20,2,20,4,20,1105,1,0");
+        let machines: Vec<Machine> = (0..32).map(|_| Machine::start(program.clone())).collect();
+        for round in 0..100 {
+            for (idx, machine) in machines.iter().enumerate() {
+                machine.input.send(idx as i64 + round).unwrap();
+            }
+            for (idx, machine) in machines.iter().enumerate() {
+                assert_eq!(Ok(2 * (idx as i64 + round)), machine.output.recv());
+            }
+        }
+        for machine in machines {
+            assert!(machine.join().is_err());
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use super::Mnemonic;
+use super::Word;
+use std::fmt;
+use std::io;
+use std::sync::mpsc::Sender;
+
+/// Everything one executed instruction did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceEvent<W = i64> {
+    pub ip: usize,
+    pub opcode: i64,
+    /// Mode of every argument, including the ones that are written to.
+    pub modes: Vec<u32>,
+    /// Values of the arguments the instruction read, after resolving their mode.
+    pub operands: Vec<W>,
+    /// Addresses written to and the value stored there.
+    pub writes: Vec<(usize, W)>,
+}
+
+impl<W: Word> TraceEvent<W> {
+    /// Renders the event as a single line of text, e.g.
+    /// `ip 0: MUL (1002) modes [0, 1, 0] operands [33, 3] writes [4]=99`.
+    pub fn to_text(&self) -> String {
+        let name = Mnemonic::find(self.opcode % 100).map_or("???", |mnemonic| mnemonic.name);
+        let mut text = format!(
+            "ip {}: {} ({}) modes {:?} operands [{}]",
+            self.ip,
+            name,
+            self.opcode,
+            self.modes,
+            join(self.operands.iter(), ", ")
+        );
+        if !self.writes.is_empty() {
+            let writes = self
+                .writes
+                .iter()
+                .map(|(address, value)| format!("[{}]={}", address, value));
+            text.push_str(&format!(" writes {}", join(writes, " ")));
+        }
+        text
+    }
+
+    /// Renders the event as a single JSON object.
+    pub fn to_json(&self) -> String {
+        let writes = self
+            .writes
+            .iter()
+            .map(|(address, value)| format!("{{\"address\":{},\"value\":{}}}", address, value));
+        format!(
+            "{{\"ip\":{},\"opcode\":{},\"modes\":[{}],\"operands\":[{}],\"writes\":[{}]}}",
+            self.ip,
+            self.opcode,
+            join(self.modes.iter(), ","),
+            join(self.operands.iter(), ","),
+            join(writes, ",")
+        )
+    }
+}
+
+fn join<T: fmt::Display>(values: impl Iterator<Item = T>, separator: &str) -> String {
+    values
+        .map(|value| value.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// Receives an event for every instruction a `Program` executes once it is installed
+/// with `Program::set_tracer`.
+pub trait Tracer<W>: Send {
+    fn trace(&mut self, event: &TraceEvent<W>);
+}
+
+/// Writes every event as a line of human readable text.
+pub struct TextTracer<T: io::Write + Send> {
+    out: T,
+}
+
+impl<T: io::Write + Send> TextTracer<T> {
+    pub fn new(out: T) -> TextTracer<T> {
+        TextTracer { out }
+    }
+}
+
+impl<W: Word, T: io::Write + Send> Tracer<W> for TextTracer<T> {
+    fn trace(&mut self, event: &TraceEvent<W>) {
+        // a failing trace sink should not stop the program it is watching
+        let _ = writeln!(self.out, "{}", event.to_text());
+    }
+}
+
+/// Writes every event as a line of JSON.
+pub struct JsonTracer<T: io::Write + Send> {
+    out: T,
+}
+
+impl<T: io::Write + Send> JsonTracer<T> {
+    pub fn new(out: T) -> JsonTracer<T> {
+        JsonTracer { out }
+    }
+}
+
+impl<W: Word, T: io::Write + Send> Tracer<W> for JsonTracer<T> {
+    fn trace(&mut self, event: &TraceEvent<W>) {
+        let _ = writeln!(self.out, "{}", event.to_json());
+    }
+}
+
+/// Sends every event down a channel, for inspecting a trace from code.
+impl<W: Word + Send> Tracer<W> for Sender<TraceEvent<W>> {
+    fn trace(&mut self, event: &TraceEvent<W>) {
+        let _ = self.send(event.clone());
+    }
+}
+
+/// The tracer installed on a `Program` plus the event of the instruction being executed.
+/// Events are only collected while a tracer is installed, and a cloned program starts
+/// without one.
+pub(crate) struct Trace<W> {
+    tracer: Option<Box<dyn Tracer<W>>>,
+    event: Option<TraceEvent<W>>,
+}
+
+impl<W: Word> Trace<W> {
+    pub fn new() -> Trace<W> {
+        Trace {
+            tracer: None,
+            event: None,
+        }
+    }
+
+    pub fn set(&mut self, tracer: Option<Box<dyn Tracer<W>>>) {
+        self.tracer = tracer;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.tracer.is_some()
+    }
+
+    pub fn begin(&mut self, ip: usize, opcode: i64) {
+        if self.tracer.is_some() {
+            self.event = Some(TraceEvent {
+                ip,
+                opcode,
+                modes: vec![],
+                operands: vec![],
+                writes: vec![],
+            });
+        }
+    }
+
+    pub fn operand(&mut self, mode: u32, value: &W) {
+        if let Some(event) = self.event.as_mut() {
+            event.modes.push(mode);
+            event.operands.push(value.clone());
+        }
+    }
+
+    pub fn write_mode(&mut self, mode: u32) {
+        if let Some(event) = self.event.as_mut() {
+            event.modes.push(mode);
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: &W) {
+        if let Some(event) = self.event.as_mut() {
+            event.writes.push((address, value.clone()));
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let (Some(tracer), Some(event)) = (self.tracer.as_mut(), self.event.take()) {
+            tracer.trace(&event);
+        }
+    }
+}
+
+impl<W> Clone for Trace<W> {
+    fn clone(&self) -> Self {
+        Trace {
+            tracer: None,
+            event: None,
+        }
+    }
+}
+
+impl<W> fmt::Debug for Trace<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Trace {{ enabled: {} }}", self.tracer.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Program;
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_trace_events() {
+        let (sender, receiver) = channel();
+        let mut prog = Program::from("1002,4,3,4,33");
+        prog.set_tracer(sender);
+        prog.execute("").unwrap();
+
+        let events: Vec<TraceEvent> = receiver.try_iter().collect();
+        assert_eq!(
+            vec![
+                TraceEvent {
+                    ip: 0,
+                    opcode: 1002,
+                    modes: vec![0, 1, 0],
+                    operands: vec![33, 3],
+                    writes: vec![(4, 99)],
+                },
+                TraceEvent {
+                    ip: 4,
+                    opcode: 99,
+                    modes: vec![],
+                    operands: vec![],
+                    writes: vec![],
+                },
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn test_trace_relative_and_output() {
+        let (sender, receiver) = channel();
+        let mut prog = Program::from("109,7,203,0,204,0,99,0");
+        prog.set_tracer(sender);
+        prog.execute("13").unwrap();
+
+        let events: Vec<TraceEvent> = receiver.try_iter().collect();
+        assert_eq!(4, events.len());
+        assert_eq!(vec![2], events[1].modes);
+        assert_eq!(vec![(7, 13)], events[1].writes);
+        assert_eq!(vec![13], events[2].operands);
+    }
+
+    #[test]
+    fn test_formatting() {
+        let event = TraceEvent {
+            ip: 0,
+            opcode: 1002,
+            modes: vec![0, 1, 0],
+            operands: vec![33, 3],
+            writes: vec![(4, 99)],
+        };
+        assert_eq!(
+            "ip 0: MUL (1002) modes [0, 1, 0] operands [33, 3] writes [4]=99",
+            event.to_text()
+        );
+        assert_eq!(
+            r#"{"ip":0,"opcode":1002,"modes":[0,1,0],"operands":[33,3],"writes":[{"address":4,"value":99}]}"#,
+            event.to_json()
+        );
+    }
+
+    #[test]
+    fn test_untraced_clone() {
+        let (sender, receiver) = channel();
+        let mut prog = Program::from("104,1,99");
+        prog.set_tracer(sender);
+
+        let mut copy = prog.clone();
+        copy.execute("").unwrap();
+        assert_eq!(0, receiver.try_iter().count());
+
+        prog.execute("").unwrap();
+        assert_eq!(2, receiver.try_iter().count());
+
+        prog.remove_tracer();
+        prog.execute("").unwrap();
+        assert_eq!(0, receiver.try_iter().count());
+    }
+}
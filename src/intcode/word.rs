@@ -0,0 +1,71 @@
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+
+/// The value type a `Program` stores in memory.
+///
+/// Arithmetic is checked so a word that is too small for a program reports an overflow
+/// instead of wrapping. Enable the `bigint` feature to run programs on `BigInt`.
+pub trait Word: Clone + Debug + Display + PartialEq + PartialOrd + FromStr {
+    fn from_i64(value: i64) -> Self;
+
+    /// Converts the word to an `i64`, or `None` when it does not fit.
+    fn to_i64(&self) -> Option<i64>;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn is_zero(&self) -> bool;
+}
+
+macro_rules! primitive_word {
+    ($t:ty) => {
+        impl Word for $t {
+            fn from_i64(value: i64) -> Self {
+                value as $t
+            }
+
+            fn to_i64(&self) -> Option<i64> {
+                Some(*self as i64)
+            }
+
+            fn checked_add(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *other)
+            }
+
+            fn checked_mul(&self, other: &Self) -> Option<Self> {
+                <$t>::checked_mul(*self, *other)
+            }
+
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+        }
+    };
+}
+
+primitive_word!(i32);
+primitive_word!(i64);
+
+#[cfg(feature = "bigint")]
+impl Word for num_bigint::BigInt {
+    fn from_i64(value: i64) -> Self {
+        num_bigint::BigInt::from(value)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        num_traits::ToPrimitive::to_i64(self)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Some(self * other)
+    }
+
+    fn is_zero(&self) -> bool {
+        num_traits::Zero::is_zero(self)
+    }
+}
@@ -1,8 +1,29 @@
+#[allow(clippy::manual_saturating_arithmetic, clippy::needless_return)]
 mod day1;
 mod day2;
+#[allow(
+    clippy::assign_op_pattern,
+    clippy::clone_on_copy,
+    clippy::collapsible_if,
+    clippy::needless_late_init,
+    clippy::needless_return,
+    clippy::redundant_closure,
+    clippy::unnecessary_cast
+)]
 mod day3;
+#[allow(
+    clippy::assign_op_pattern,
+    clippy::bool_assert_comparison,
+    clippy::bool_comparison,
+    clippy::clone_on_copy,
+    clippy::len_zero,
+    clippy::needless_borrow,
+    clippy::needless_return,
+    clippy::ptr_arg
+)]
 mod day4;
 mod day5;
+mod tools;
 
 use std::env;
 
@@ -12,14 +33,34 @@ fn main() {
     let day = &args[1];
     let filename = &args[2];
 
-    println!("In file {}", filename);
-
-    match day.parse::<u8>().unwrap_or(0) {
-        1 => day1::solution(filename),
-        2 => day2::solution(filename),
-        3 => day3::solution(filename),
-        4 => day4::solution(filename),
-        5 => day5::solution(filename),
-        _ => panic!("unknown day number"),
+    match day.as_str() {
+        "arcade" => tools::arcade(filename, args.get(3).is_some_and(|mode| mode == "watch")),
+        "assemble" => tools::assemble(filename),
+        "bench" => tools::bench(filename),
+        "check" => tools::check(filename, args.get(3).map_or("", |input| input.as_str())),
+        "debug" => tools::debug(filename),
+        "disassemble" => tools::disassemble(filename),
+        "profile" => tools::profile(
+            filename,
+            args.get(3).map_or("report", |format| format.as_str()),
+            args.get(4).map_or("", |input| input.as_str()),
+        ),
+        "trace" => tools::trace(
+            filename,
+            args.get(3).map_or("text", |format| format.as_str()),
+            args.get(4).map_or("", |input| input.as_str()),
+        ),
+        _ => {
+            // the tools only print what they produce, so it can be piped elsewhere
+            println!("In file {}", filename);
+            match day.parse::<u8>().unwrap_or(0) {
+                1 => day1::solution(filename),
+                2 => day2::solution(filename),
+                3 => day3::solution(filename),
+                4 => day4::solution(filename),
+                5 => day5::solution(filename),
+                _ => panic!("unknown day number"),
+            }
+        }
     }
 }
@@ -0,0 +1,162 @@
+use advent_of_rust_2019::intcode::arcade::{Arcade, Tile};
+use advent_of_rust_2019::intcode::debugger::Debugger;
+use advent_of_rust_2019::intcode::trace::{JsonTracer, TextTracer};
+use advent_of_rust_2019::intcode::{assembler, compiler, disassembler, Program};
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+pub fn disassemble(filename: &String) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    match disassembler::disassemble(&contents) {
+        Ok(listing) => print!("{}", listing),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+pub fn assemble(filename: &String) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    match assembler::assemble(&contents) {
+        Ok(program) => println!("{}", program),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+/// Runs a program with a comma separated `input`, writing a trace of every executed
+/// instruction to stderr as `text` or `json` lines.
+pub fn trace(filename: &String, format: &str, input: &str) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    let mut prog = Program::from(contents.as_str());
+    match format {
+        "json" => prog.set_tracer(JsonTracer::new(io::stderr())),
+        _ => prog.set_tracer(TextTracer::new(io::stderr())),
+    }
+
+    match prog.execute(input) {
+        Ok(output) => print!("{}", output),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+/// Runs a program with a comma separated `input` and lists every write it makes to an
+/// address that is executed as code.
+pub fn check(filename: &String, input: &str) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    let mut prog = Program::from(contents.as_str());
+    prog.track_code_writes();
+    if let Err(error) = prog.execute(input) {
+        eprintln!("{}", error);
+    }
+
+    if prog.code_writes().is_empty() {
+        println!("no writes to code");
+    }
+    for write in prog.code_writes() {
+        println!("{}", write);
+    }
+}
+
+/// Runs a program with a comma separated `input` and prints where it spent its time,
+/// as a `report` or as `folded` stacks for flamegraph tools.
+pub fn profile(filename: &String, format: &str, input: &str) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    let mut prog = Program::from(contents.as_str());
+    prog.enable_profiling();
+    if let Err(error) = prog.execute(input) {
+        eprintln!("{}", error);
+    }
+
+    let profile = prog.profile().unwrap();
+    match format {
+        "folded" => print!("{}", profile.folded()),
+        _ => print!("{}", profile.report(&prog, 10)),
+    }
+}
+
+/// Counts the blocks the arcade game in `filename` draws, then plays it for free with
+/// the autoplay joystick and prints the final screen. With `watch` every frame is drawn.
+pub fn arcade(filename: &String, watch: bool) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+    let program = Program::from(contents.as_str());
+
+    let mut arcade = Arcade::new(program.clone());
+    match arcade.run() {
+        Ok(()) => println!("{} blocks", arcade.count(Tile::Block)),
+        Err(error) => eprintln!("{}", error),
+    }
+
+    let mut arcade = Arcade::new(program);
+    arcade.insert_quarters(2);
+    let mut result = arcade.run();
+    while result.is_ok() && !arcade.halted() {
+        if watch {
+            print!("\x1b[2J\x1b[H{}", arcade.render());
+        }
+        result = arcade.joystick(arcade.track_ball());
+    }
+    match result {
+        Ok(()) => print!("{}", arcade.render()),
+        Err(error) => eprintln!("{}", error),
+    }
+}
+
+pub fn debug(filename: &String) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+
+    let mut debugger = Debugger::new(Program::from(contents.as_str()));
+    let stdin = io::stdin();
+    debugger
+        .repl(stdin.lock(), io::stdout())
+        .expect("Something went wrong talking to the terminal");
+}
+
+/// Counts down from its input; one pass of the loop executes two instructions.
+const COUNTDOWN: &str = "3,100,1001,100,-1,100,1005,100,2,99";
+
+/// Times the interpreter and the compiled program on the program in `filename`, run
+/// repeatedly with input `5` like day 5, and on a long running loop.
+pub fn bench(filename: &String) {
+    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
+    let program = Program::from(contents.as_str());
+    let compiled = compiler::compile(&program);
+
+    let runs = 10_000;
+    let start = Instant::now();
+    for _ in 0..runs {
+        program.clone().execute("5").unwrap();
+    }
+    report(&format!("{} runs of {}", runs, filename), start, runs);
+
+    let start = Instant::now();
+    for _ in 0..runs {
+        compiled.execute(&mut program.clone(), "5").unwrap();
+    }
+    report(
+        &format!("{} compiled runs of {}", runs, filename),
+        start,
+        runs,
+    );
+
+    let count = 10_000_000;
+    let instructions = 2 * count + 2;
+    let countdown = Program::from(COUNTDOWN);
+    let start = Instant::now();
+    countdown.clone().execute(&count.to_string()).unwrap();
+    report("countdown loop", start, instructions);
+
+    let start = Instant::now();
+    compiler::compile(&countdown)
+        .execute(&mut countdown.clone(), &count.to_string())
+        .unwrap();
+    report("compiled countdown loop", start, instructions);
+}
+
+fn report(name: &str, start: Instant, count: u32) {
+    let elapsed = start.elapsed();
+    println!("{} in {:?} ({:?} each)", name, elapsed, elapsed / count);
+}